@@ -0,0 +1,49 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// Rows of a picture, e.g. the CRT output of day 10
+    Picture(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answers {
+    pub part1: Answer,
+    pub part2: Answer,
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(number) => write!(f, "{number}"),
+            Answer::Text(text) => write!(f, "{text}"),
+            Answer::Picture(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(number: i32) -> Answer {
+        Answer::Number(i64::from(number))
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(number: u32) -> Answer {
+        Answer::Number(i64::from(number))
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(number: usize) -> Answer {
+        Answer::Number(number as i64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text: String) -> Answer {
+        Answer::Text(text)
+    }
+}
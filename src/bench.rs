@@ -0,0 +1,240 @@
+use super::output::{render_table, Align};
+use super::parse::ParseError;
+use super::solutions::{Day, Timings};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    Parse,
+    PartOne,
+    PartTwo,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 3] = [Phase::Parse, Phase::PartOne, Phase::PartTwo];
+
+    fn of(self, timings: &Timings) -> Duration {
+        match self {
+            Phase::Parse => timings.parse,
+            Phase::PartOne => timings.part_one,
+            Phase::PartTwo => timings.part_two,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Phase> {
+        Phase::ALL
+            .into_iter()
+            .find(|phase| phase.to_string() == name)
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Parse => write!(f, "parse"),
+            Phase::PartOne => write!(f, "part1"),
+            Phase::PartTwo => write!(f, "part2"),
+        }
+    }
+}
+
+/// Summary of repeated measurements, in nanoseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut nanos: Vec<f64> = samples.iter().map(|d| d.as_nanos() as f64).collect();
+        nanos.sort_by(|a, b| a.total_cmp(b));
+        let count = nanos.len().max(1) as f64;
+        let mean = nanos.iter().sum::<f64>() / count;
+        let median = match nanos.len() {
+            0 => 0.0,
+            len if len % 2 == 0 => (nanos[len / 2 - 1] + nanos[len / 2]) / 2.0,
+            len => nanos[len / 2],
+        };
+        let variance = nanos.iter().map(|n| (n - mean).powi(2)).sum::<f64>() / count;
+        Stats {
+            mean,
+            median,
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+/// Measurements of every phase, keyed by day and phase
+pub type Results = HashMap<(u8, Phase), Stats>;
+
+/// Runs `day` once to warm up, then `iterations` more times, timing each phase separately
+pub fn bench_day(
+    day: &Day,
+    input: &str,
+    iterations: usize,
+) -> Result<HashMap<Phase, Stats>, ParseError> {
+    day.run(input)?;
+    let mut samples: HashMap<Phase, Vec<Duration>> = HashMap::new();
+    for _ in 0..iterations.max(1) {
+        let timings = day.run(input)?.timings;
+        for phase in Phase::ALL {
+            samples.entry(phase).or_default().push(phase.of(&timings));
+        }
+    }
+
+    Ok(samples
+        .into_iter()
+        .map(|(phase, samples)| (phase, Stats::from_samples(&samples)))
+        .collect())
+}
+
+fn format_nanos(nanos: f64) -> String {
+    format!("{:.1?}", Duration::from_nanos(nanos.round() as u64))
+}
+
+pub fn render_results(results: &Results, baseline: Option<&Results>) -> String {
+    let mut keys: Vec<&(u8, Phase)> = results.keys().collect();
+    keys.sort();
+
+    let rows: Vec<Vec<String>> = keys
+        .into_iter()
+        .map(|key| {
+            let stats = results[key];
+            let change = baseline
+                .and_then(|baseline| baseline.get(key))
+                .map(|before| {
+                    if before.median == 0.0 {
+                        String::from("n/a")
+                    } else {
+                        format!("{:+.1}%", (stats.median / before.median - 1.0) * 100.0)
+                    }
+                })
+                .unwrap_or_default();
+            vec![
+                key.0.to_string(),
+                key.1.to_string(),
+                format_nanos(stats.mean),
+                format_nanos(stats.median),
+                format!("±{}", format_nanos(stats.stddev)),
+                change,
+            ]
+        })
+        .collect();
+
+    render_table(
+        &[
+            ("Day", Align::Right),
+            ("Phase", Align::Left),
+            ("Mean", Align::Right),
+            ("Median", Align::Right),
+            ("Std dev", Align::Right),
+            ("Change", Align::Right),
+        ],
+        &rows,
+    )
+}
+
+/// Serializes results as TSV, so a baseline can be saved and compared against later
+pub fn write_baseline(results: &Results) -> String {
+    let mut keys: Vec<&(u8, Phase)> = results.keys().collect();
+    keys.sort();
+
+    let mut output = String::from("day\tphase\tmean_ns\tmedian_ns\tstddev_ns\n");
+    for key in keys {
+        let stats = results[key];
+        output.push_str(&format!(
+            "{}\t{}\t{:.0}\t{:.0}\t{:.0}\n",
+            key.0, key.1, stats.mean, stats.median, stats.stddev
+        ));
+    }
+    output
+}
+
+pub fn read_baseline(text: &str) -> Result<Results, String> {
+    let mut results = Results::new();
+    for (idx, line) in text.lines().enumerate().skip(1) {
+        let error = || format!("line {}: malformed baseline entry `{line}`", idx + 1);
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [day, phase, mean, median, stddev] = fields.as_slice() else {
+            return Err(error());
+        };
+        let day = day.parse().map_err(|_| error())?;
+        let phase = Phase::from_name(phase).ok_or_else(error)?;
+        let number = |field: &str| field.parse::<f64>().map_err(|_| error());
+        results.insert(
+            (day, phase),
+            Stats {
+                mean: number(mean)?,
+                median: number(median)?,
+                stddev: number(stddev)?,
+            },
+        );
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_stats() {
+        let samples = [2, 4, 4, 4, 5, 5, 7, 9].map(Duration::from_nanos);
+        assert_eq!(
+            Stats::from_samples(&samples),
+            Stats {
+                mean: 5.0,
+                median: 4.5,
+                stddev: 2.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_baseline_roundtrip() {
+        let results = Results::from([
+            (
+                (1, Phase::Parse),
+                Stats {
+                    mean: 1500.0,
+                    median: 1400.0,
+                    stddev: 20.0,
+                },
+            ),
+            (
+                (1, Phase::PartTwo),
+                Stats {
+                    mean: 300.0,
+                    median: 250.0,
+                    stddev: 5.0,
+                },
+            ),
+        ]);
+        let text = write_baseline(&results);
+        assert_eq!(
+            text,
+            "day\tphase\tmean_ns\tmedian_ns\tstddev_ns\n1\tparse\t1500\t1400\t20\n1\tpart2\t300\t250\t5\n"
+        );
+        assert_eq!(read_baseline(&text), Ok(results));
+    }
+
+    #[test]
+    fn test_render_change() {
+        let stats = |median| Stats {
+            mean: median,
+            median,
+            stddev: 0.0,
+        };
+        let results = Results::from([((3, Phase::PartOne), stats(1500.0))]);
+        let baseline = Results::from([((3, Phase::PartOne), stats(1000.0))]);
+        assert_eq!(
+            render_results(&results, Some(&baseline)),
+            "Day  Phase   Mean  Median  Std dev  Change\n  3  part1  1.5µs   1.5µs   ±0.0ns  +50.0%\n"
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use super::input::default_input_path;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const SESSION_ENV: &str = "AOC_SESSION";
+pub const DEFAULT_SESSION_FILE: &str = ".aoc-session";
+/// Minimum time between two requests to the server
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+const USER_AGENT: &str = "github.com/milch/aoc2022 (advent_of_code_2022 fetch)";
+/// Remembers when the last request was made, inside the input directory
+const LAST_FETCH_FILE: &str = ".last-fetch";
+
+#[derive(Debug)]
+pub enum FetchError {
+    NoSession(PathBuf),
+    InvalidUrl(String),
+    Http { status: u16, message: String },
+    Transport(String),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::NoSession(path) => write!(
+                f,
+                "no session token found, set {SESSION_ENV} or put it into `{}`",
+                path.display()
+            ),
+            FetchError::InvalidUrl(url) => write!(f, "unsupported URL `{url}`"),
+            FetchError::Http { status, message } => {
+                write!(f, "server responded with {status}: {message}")
+            }
+            FetchError::Transport(message) => write!(f, "request failed: {message}"),
+            FetchError::Io(path, err) => write!(f, "could not access `{}`: {err}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Performs a GET request authenticated with the session cookie, returning the body
+pub trait HttpClient {
+    fn get(&self, url: &str, session: &str) -> Result<String, FetchError>;
+}
+
+/// Minimal HTTP/1.0 client for plain `http://` URLs, such as a local stand-in server
+pub struct PlainHttp;
+
+impl HttpClient for PlainHttp {
+    fn get(&self, url: &str, session: &str) -> Result<String, FetchError> {
+        let invalid = || FetchError::InvalidUrl(String::from(url));
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (host, path) = match rest.find('/') {
+            Some(idx) => rest.split_at(idx),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        let address = if host.contains(':') {
+            String::from(host)
+        } else {
+            format!("{host}:80")
+        };
+
+        let transport = |err: io::Error| FetchError::Transport(format!("{host}: {err}"));
+        let mut stream = TcpStream::connect(&address).map_err(transport)?;
+        let request = format!(
+            "GET {path} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: {USER_AGENT}\r\nCookie: session={session}\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).map_err(transport)?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(transport)?;
+
+        let malformed = || FetchError::Transport(format!("{host}: malformed response"));
+        let (head, body) = response.split_once("\r\n\r\n").ok_or_else(malformed)?;
+        let status: u16 = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(malformed)?;
+        if status != 200 {
+            return Err(FetchError::Http {
+                status,
+                message: String::from(body.lines().next().unwrap_or_default().trim()),
+            });
+        }
+        Ok(String::from(body))
+    }
+}
+
+/// Delegates to the `curl` binary, which takes care of TLS
+pub struct Curl;
+
+impl HttpClient for Curl {
+    fn get(&self, url: &str, session: &str) -> Result<String, FetchError> {
+        // The cookie is passed on stdin, so the token does not show up in the process list
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--header", "@-"])
+            .args(["--user-agent", USER_AGENT, url])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| FetchError::Transport(format!("could not run curl: {err}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "Cookie: session={session}")
+                .map_err(|err| FetchError::Transport(err.to_string()))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|err| FetchError::Transport(err.to_string()))?;
+        if !output.status.success() {
+            return Err(FetchError::Transport(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| FetchError::Transport(String::from("response is not valid UTF-8")))
+    }
+}
+
+/// Picks a client able to talk to `base_url`
+pub fn client_for(base_url: &str) -> Result<Box<dyn HttpClient>, FetchError> {
+    if base_url.starts_with("http://") {
+        Ok(Box::new(PlainHttp))
+    } else if base_url.starts_with("https://") {
+        Ok(Box::new(Curl))
+    } else {
+        Err(FetchError::InvalidUrl(String::from(base_url)))
+    }
+}
+
+/// Reads the session token from the environment, falling back to `file`
+pub fn load_session(file: &Path) -> Result<String, FetchError> {
+    let token = match std::env::var(SESSION_ENV) {
+        Ok(token) if !token.trim().is_empty() => token,
+        _ => fs::read_to_string(file).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => FetchError::NoSession(file.to_path_buf()),
+            _ => FetchError::Io(file.to_path_buf(), err),
+        })?,
+    };
+    let token = token.trim();
+    let token = token.strip_prefix("session=").unwrap_or(token);
+    if token.is_empty() {
+        return Err(FetchError::NoSession(file.to_path_buf()));
+    }
+    Ok(String::from(token))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fetched {
+    Downloaded(PathBuf),
+    /// The input was already on disk, so nothing was requested
+    Cached(PathBuf),
+}
+
+pub struct Fetcher {
+    pub client: Box<dyn HttpClient>,
+    pub base_url: String,
+    pub session: String,
+    pub input_dir: PathBuf,
+    pub min_interval: Duration,
+}
+
+impl Fetcher {
+    pub fn input_url(&self, day: u8) -> String {
+        format!(
+            "{}/2022/day/{day}/input",
+            self.base_url.trim_end_matches('/')
+        )
+    }
+
+    /// Downloads the input for `day` into the input directory, unless it is already there.
+    /// `force` downloads it again and overwrites the existing file.
+    pub fn fetch(&self, day: u8, force: bool) -> Result<Fetched, FetchError> {
+        let path = default_input_path(&self.input_dir, day);
+        if path.exists() && !force {
+            return Ok(Fetched::Cached(path));
+        }
+
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |err| FetchError::Io(path, err)
+        };
+        fs::create_dir_all(&self.input_dir).map_err(io_error(&self.input_dir))?;
+        self.wait_for_turn()?;
+        let input = self.client.get(&self.input_url(day), &self.session)?;
+        fs::write(&path, input).map_err(io_error(&path))?;
+        Ok(Fetched::Downloaded(path))
+    }
+
+    /// Sleeps until `min_interval` has passed since the previous request, then records this one
+    fn wait_for_turn(&self) -> Result<(), FetchError> {
+        let stamp = self.input_dir.join(LAST_FETCH_FILE);
+        let last = fs::read_to_string(&stamp)
+            .ok()
+            .and_then(|text| text.trim().parse().ok())
+            .map(Duration::from_millis);
+        if let Some(last) = last {
+            thread::sleep(wait_time(last, since_epoch(), self.min_interval));
+        }
+
+        fs::write(&stamp, since_epoch().as_millis().to_string())
+            .map_err(|err| FetchError::Io(stamp, err))
+    }
+}
+
+fn since_epoch() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+fn wait_time(last: Duration, now: Duration, min_interval: Duration) -> Duration {
+    min_interval.saturating_sub(now.saturating_sub(last))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::cell::RefCell;
+    use std::net::TcpListener;
+    use std::rc::Rc;
+
+    /// Records the requested URLs instead of sending them
+    struct FakeClient(Rc<RefCell<Vec<String>>>);
+
+    impl HttpClient for FakeClient {
+        fn get(&self, url: &str, _session: &str) -> Result<String, FetchError> {
+            self.0.borrow_mut().push(String::from(url));
+            Ok(String::from("1\n2\n3\n"))
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-fetch-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_fetch_caches_input() {
+        let requests = Rc::new(RefCell::new(vec![]));
+        let dir = temp_dir("cache");
+        let fetcher = Fetcher {
+            client: Box::new(FakeClient(Rc::clone(&requests))),
+            base_url: String::from("http://localhost:1/"),
+            session: String::from("token"),
+            input_dir: dir.clone(),
+            min_interval: Duration::ZERO,
+        };
+        let path = dir.join("day03.txt");
+
+        assert_eq!(
+            fetcher.fetch(3, false).unwrap(),
+            Fetched::Downloaded(path.clone())
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1\n2\n3\n");
+        assert_eq!(
+            fetcher.fetch(3, false).unwrap(),
+            Fetched::Cached(path.clone())
+        );
+        assert_eq!(fetcher.fetch(3, true).unwrap(), Fetched::Downloaded(path));
+        assert_eq!(
+            *requests.borrow(),
+            vec![String::from("http://localhost:1/2022/day/3/input"); 2]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_plain_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let responses = [
+                "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\n\r\nA Y\nB X\n",
+                "HTTP/1.0 404 Not Found\r\n\r\nPlease don't repeatedly request this endpoint before it unlocks!\n",
+            ];
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = String::new();
+                let mut buffer = [0; 256];
+                while !request.ends_with("\r\n\r\n") {
+                    let len = stream.read(&mut buffer).unwrap();
+                    request.push_str(&String::from_utf8_lossy(&buffer[..len]));
+                }
+                requests.push(request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        let url = format!("http://{address}/2022/day/2/input");
+        assert_eq!(PlainHttp.get(&url, "secret").unwrap(), "A Y\nB X\n");
+        assert!(matches!(
+            PlainHttp.get(&url, "secret"),
+            Err(FetchError::Http { status: 404, .. })
+        ));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /2022/day/2/input HTTP/1.0\r\n"));
+        assert!(requests[0].contains("\r\nCookie: session=secret\r\n"));
+    }
+
+    #[test]
+    fn test_wait_time() {
+        let interval = Duration::from_secs(5);
+        let at = Duration::from_secs;
+        assert_eq!(wait_time(at(100), at(102), interval), at(3));
+        assert_eq!(wait_time(at(100), at(110), interval), Duration::ZERO);
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_INPUT_DIR: &str = "inputs";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// Look up `dayNN.txt` inside the given directory
+    Directory(PathBuf),
+    File(PathBuf),
+    Stdin,
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Missing(PathBuf),
+    Io(PathBuf, io::Error),
+    Stdin(io::Error),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Missing(path) => write!(
+                f,
+                "no input found at `{}` (pass --input <path> or --stdin to read it from elsewhere)",
+                path.display()
+            ),
+            InputError::Io(path, err) => write!(f, "could not read `{}`: {err}", path.display()),
+            InputError::Stdin(err) => write!(f, "could not read input from stdin: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+pub fn default_input_path(dir: &Path, day: u8) -> PathBuf {
+    dir.join(format!("day{day:02}.txt"))
+}
+
+impl InputSource {
+    pub fn load(&self, day: u8) -> Result<String, InputError> {
+        match self {
+            InputSource::Directory(dir) => read_file(&default_input_path(dir, day)),
+            InputSource::File(path) => read_file(path),
+            InputSource::Stdin => {
+                let mut input = String::new();
+                io::stdin()
+                    .read_to_string(&mut input)
+                    .map_err(InputError::Stdin)?;
+                Ok(input)
+            }
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, InputError> {
+    fs::read_to_string(path).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => InputError::Missing(path.to_path_buf()),
+        _ => InputError::Io(path.to_path_buf(), err),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_default_input_path() {
+        assert_eq!(
+            default_input_path(Path::new("inputs"), 7),
+            PathBuf::from("inputs/day07.txt")
+        );
+        assert_eq!(
+            default_input_path(Path::new("inputs"), 10),
+            PathBuf::from("inputs/day10.txt")
+        );
+    }
+
+    #[test]
+    fn test_missing_input_names_path() {
+        let source = InputSource::Directory(PathBuf::from("does/not/exist"));
+        let err = source.load(3).unwrap_err();
+        assert!(matches!(err, InputError::Missing(_)));
+        assert!(err.to_string().contains("does/not/exist/day03.txt"));
+    }
+}
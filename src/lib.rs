@@ -0,0 +1,25 @@
+//! Advent of Code 2022 solutions, plus the shared input, answer and tree utilities they use.
+//!
+//! Every day implements [`solutions::Solution`], so it can be run programmatically:
+//!
+//! ```
+//! use advent_of_code_2022::solutions::{self, day_02::Day02, Solution};
+//!
+//! let strategy = Day02::parse("A Y\nB X\nC Z\n").unwrap();
+//! assert_eq!(Day02::part_one(&strategy), 15);
+//!
+//! let run = solutions::find(2).unwrap().run("A Y\nB X\nC Z\n").unwrap();
+//! assert_eq!(run.answers.part2.to_string(), "12");
+//! ```
+
+pub mod answer;
+pub mod bench;
+pub mod fetch;
+pub mod input;
+pub mod output;
+pub mod parse;
+pub mod runner;
+pub mod scaffold;
+pub mod solutions;
+pub mod tree;
+pub mod verify;
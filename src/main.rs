@@ -1,29 +1,587 @@
-pub mod solutions;
-pub mod tree;
-
-use clap::arg;
-use clap::Parser;
-use solutions::*;
+use advent_of_code_2022::answer::Answers;
+use advent_of_code_2022::input::{self, InputSource};
+use advent_of_code_2022::output::{self, OutputFormat};
+use advent_of_code_2022::parse::ParseError;
+use advent_of_code_2022::runner::{self, DayList, Outcome};
+use advent_of_code_2022::solutions::day_07::{self, UnseenDirectories};
+use advent_of_code_2022::tree::disk::{self, ScanOptions};
+use advent_of_code_2022::tree::render::{self, RenderOptions};
+use advent_of_code_2022::tree::{CleanupOptions, FSNode, FSPath, Objective, Query};
+use advent_of_code_2022::{bench, fetch, scaffold, solutions, verify};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process;
 
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(long, required_unless_present_any = ["all", "days", "verify", "bench"], conflicts_with_all = ["all", "days"])]
+    day: Option<u8>,
+
+    /// Run every registered day and print a summary with timings
+    #[arg(long, conflicts_with_all = ["days", "input", "stdin"])]
+    all: bool,
+
+    /// Run a list of days such as `3,5,7-9` and print a summary with timings
+    #[arg(long, conflicts_with_all = ["input", "stdin"])]
+    days: Option<DayList>,
+
+    /// Read the puzzle input from this file
+    #[arg(long, conflicts_with = "stdin")]
+    input: Option<PathBuf>,
+
+    /// Read the puzzle input from stdin
+    #[arg(long)]
+    stdin: bool,
+
+    /// Directory containing `dayNN.txt` inputs, used when neither --input nor --stdin is given
+    #[arg(long, global = true, default_value = input::DEFAULT_INPUT_DIR)]
+    input_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Check the answers of the selected days (all by default) against the answers file
+    #[arg(long)]
+    verify: bool,
+
+    /// File with known-correct answers, used by --verify
+    #[arg(long, default_value = verify::DEFAULT_ANSWERS_FILE)]
+    answers: PathBuf,
+
+    /// Time the parse and solve phases of the selected days (all by default) over N runs
+    #[arg(long, value_name = "N", conflicts_with = "verify")]
+    bench: Option<usize>,
+
+    /// Save the --bench results to this file
+    #[arg(long, requires = "bench")]
+    save_baseline: Option<PathBuf>,
+
+    /// Compare the --bench results against a previously saved baseline
+    #[arg(long, requires = "bench")]
+    baseline: Option<PathBuf>,
+
+    /// Print the filesystem reconstructed by day 7 before the answers
+    #[arg(long, value_enum, requires = "day")]
+    fs_view: Option<FsView>,
+
+    /// Don't show --fs-view entries deeper than this
+    #[arg(long, requires = "fs_view")]
+    max_depth: Option<usize>,
+
+    /// Show --fs-view sizes like `1.5M`
+    #[arg(long, requires = "fs_view")]
+    human_readable: bool,
+
+    /// Hide --fs-view entries smaller than this, e.g. `100k`
+    #[arg(long, requires = "fs_view", value_parser = parse_min_size)]
+    min_size: Option<usize>,
+
+    /// Print the result of a query like `sum type:dir size<100k` over the day 7 filesystem
+    #[arg(long, requires = "day", value_parser = parse_query)]
+    query: Option<Query>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FsView {
+    /// Indented like the `tree` command
+    Tree,
+    /// Directories sorted by total size, like `du`
+    Du,
+    /// The whole tree as JSON, ignoring the display options
+    Json,
+    /// One entry per line, ignoring the display options
+    Lines,
+    /// A day 7 terminal session of `cd` and `ls` commands
+    Transcript,
+}
+
+impl FsView {
+    fn render(self, root: &FSNode, options: &RenderOptions) -> String {
+        match self {
+            FsView::Tree => root.render_tree(options),
+            FsView::Du => root.render_du(options),
+            FsView::Json => root.to_json(),
+            FsView::Lines => root.to_lines(),
+            FsView::Transcript => root.to_transcript(),
+        }
+    }
+}
+
+fn parse_query(text: &str) -> Result<Query, String> {
+    text.parse()
+        .map_err(|err: ParseError| format!("invalid query\n{err}"))
+}
+
+fn parse_min_size(text: &str) -> Result<usize, String> {
+    render::parse_size(text).ok_or_else(|| format!("`{text}` is not a size like `1234` or `100k`"))
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Download puzzle inputs into the input directory
+    Fetch(FetchArgs),
+    /// Generate and register a placeholder module for a new day
+    New(NewArgs),
+    /// Show the sizes of a directory on disk, as day 7 does for its transcript
+    Disk(DiskArgs),
+    /// Compare two filesystems, each a directory on disk or a saved tree
+    Diff(DiffArgs),
+}
+
+#[derive(Args, Debug)]
+struct DiffArgs {
+    /// A directory, a day 7 transcript, or a tree saved with `--view json` or `--view lines`
+    old: PathBuf,
+    new: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct DiskArgs {
+    path: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = FsView::Du)]
+    view: FsView,
+
+    /// Descend into symlinked directories and count symlinked files
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Don't descend into other mounted filesystems
+    #[arg(long)]
+    one_file_system: bool,
+
+    /// Skip entries whose name matches a pattern like `*.log`, or whose path below PATH matches
+    /// a pattern containing a `/`. Can be given several times.
+    #[arg(long, value_name = "PATTERN")]
+    ignore: Vec<String>,
+
+    /// Plan deletions so that this much is free afterwards, e.g. `1G`
+    #[arg(long, value_parser = parse_min_size)]
+    required: Option<usize>,
+
+    /// Size of the disk for --required, the space taken by PATH if not given
+    #[arg(long, requires = "required", value_parser = parse_min_size)]
+    capacity: Option<usize>,
+
+    #[arg(long, requires = "required", value_enum, default_value_t = Objective::FewestDeletions)]
+    objective: Objective,
+
+    /// Never delete this path below PATH or anything inside it. Can be given several times.
+    #[arg(long, requires = "required", value_name = "PATH")]
+    protect: Vec<String>,
+
+    /// Don't show entries deeper than this
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Show sizes like `1.5M`
+    #[arg(long)]
+    human_readable: bool,
+
+    /// Hide entries smaller than this, e.g. `100k`
+    #[arg(long, value_parser = parse_min_size)]
+    min_size: Option<usize>,
+
+    /// Print the result of a query like `sum type:dir size<100k` instead of a view
+    #[arg(long, value_parser = parse_query)]
+    query: Option<Query>,
+}
+
+#[derive(Args, Debug)]
+struct NewArgs {
     #[arg(long)]
     day: u8,
+
+    /// Source directory containing `solutions.rs`
+    #[arg(long, default_value = scaffold::DEFAULT_SRC_DIR)]
+    src_dir: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct FetchArgs {
+    #[arg(long, required_unless_present = "days", conflicts_with = "days")]
+    day: Option<u8>,
+
+    /// Fetch a list of days such as `3,5,7-9`
+    #[arg(long)]
+    days: Option<DayList>,
+
+    /// Download the input again, even if it is already on disk
+    #[arg(long)]
+    force: bool,
+
+    /// File containing the session token, used when AOC_SESSION is not set
+    #[arg(long, default_value = fetch::DEFAULT_SESSION_FILE)]
+    session_file: PathBuf,
+
+    /// Server to download from, e.g. a local stand-in for testing
+    #[arg(long, default_value = fetch::DEFAULT_BASE_URL)]
+    base_url: String,
+}
+
+impl Cli {
+    fn selected_days(&self) -> Vec<u8> {
+        match (self.day, &self.days) {
+            (Some(day), _) => vec![day],
+            (None, Some(DayList(days))) => days.clone(),
+            (None, None) => solutions::available(),
+        }
+    }
+
+    fn input_source(&self) -> InputSource {
+        match (&self.input, self.stdin) {
+            (Some(path), _) => InputSource::File(path.clone()),
+            (None, true) => InputSource::Stdin,
+            (None, false) => InputSource::Directory(self.input_dir.clone()),
+        }
+    }
 }
 
 fn main() {
     let args = Cli::parse();
-    match args.day {
-        1 => day_01::print_solution(),
-        2 => day_02::print_solution(),
-        3 => day_03::print_solution(),
-        4 => day_04::print_solution(),
-        5 => day_05::print_solution(),
-        6 => day_06::print_solution(),
-        7 => day_07::print_solution(),
-        8 => day_08::print_solution(),
-        9 => day_09::print_solution(),
-        10 => day_10::print_solution(),
-        _ => unimplemented!(),
+    match &args.command {
+        Some(Command::Fetch(fetch_args)) => return fetch_inputs(&args, fetch_args),
+        Some(Command::New(new_args)) => return new_day(&args, new_args),
+        Some(Command::Disk(disk_args)) => return show_disk(disk_args),
+        Some(Command::Diff(diff_args)) => return show_diff(diff_args),
+        None => {}
+    }
+    if args.verify {
+        return verify_days(&args, &args.selected_days());
+    }
+    if let Some(iterations) = args.bench {
+        return bench_days(&args, &args.selected_days(), iterations);
+    }
+
+    match (args.day, &args.days) {
+        (Some(day), _) => run_single_day(&args, day),
+        (None, Some(DayList(days))) => run_many_days(&args, days),
+        (None, None) => run_many_days(&args, &solutions::available()),
+    }
+}
+
+fn run_single_day(args: &Cli, number: u8) {
+    let Some(day) = solutions::find(number) else {
+        let available: Vec<String> = solutions::available()
+            .iter()
+            .map(|day| day.to_string())
+            .collect();
+        eprintln!(
+            "error: day {number} has no solution (available days: {})",
+            available.join(", ")
+        );
+        process::exit(1);
+    };
+
+    let input = match args.input_source().load(day.number) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("error: {err}");
+            process::exit(1);
+        }
+    };
+
+    if args.fs_view.is_some() || args.query.is_some() {
+        show_filesystem(args, number, &input);
+    }
+
+    let run = match day.run(&input) {
+        Ok(run) => run,
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    };
+    print!(
+        "{}",
+        output::render(args.format, &[(day.number, run.answers)])
+    );
+}
+
+fn show_filesystem(args: &Cli, number: u8, input: &str) {
+    if number != 7 {
+        eprintln!("error: --fs-view and --query only apply to day 7");
+        process::exit(1);
+    }
+    let session = match day_07::interpret(input, UnseenDirectories::Error) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("{}", err.with_day(number));
+            process::exit(1);
+        }
+    };
+
+    let options = RenderOptions {
+        max_depth: args.max_depth,
+        human_readable: args.human_readable,
+        min_size: args.min_size.unwrap_or_default(),
+    };
+    if let Some(view) = args.fs_view {
+        print!("{}", view.render(&session.root, &options));
+    }
+    if let Some(query) = &args.query {
+        print!("{}", session.root.query(query));
+    }
+    if !session.unlisted.is_empty() {
+        let unlisted: Vec<String> = session.unlisted.iter().map(|p| p.to_string()).collect();
+        eprintln!(
+            "warning: never listed, so their sizes may be incomplete: {}",
+            unlisted.join(", ")
+        );
+    }
+}
+
+/// Runs the days without the default panic hook, since the summary already reports panics
+fn run_quietly(args: &Cli, days: &[u8]) -> Vec<(u8, Outcome)> {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let outcomes = runner::run_days(days, &args.input_source());
+    panic::set_hook(previous_hook);
+    outcomes
+}
+
+fn run_many_days(args: &Cli, days: &[u8]) {
+    let outcomes = run_quietly(args, days);
+    match args.format {
+        OutputFormat::Text => print!("{}", runner::render_summary(&outcomes)),
+        format => {
+            let solved: Vec<(u8, Answers)> = outcomes
+                .iter()
+                .filter_map(|(day, outcome)| match outcome {
+                    Outcome::Solved(run) => Some((*day, run.answers.clone())),
+                    _ => None,
+                })
+                .collect();
+            print!("{}", output::render(format, &solved));
+            for problem in runner::problems(&outcomes) {
+                eprintln!("{problem}");
+            }
+        }
+    }
+
+    if outcomes
+        .iter()
+        .any(|(_, outcome)| matches!(outcome, Outcome::Invalid(_) | Outcome::Failed(_)))
+    {
+        process::exit(1);
+    }
+}
+
+fn verify_days(args: &Cli, days: &[u8]) {
+    let key = fs::read_to_string(&args.answers)
+        .map_err(|err| err.to_string())
+        .and_then(|text| verify::parse_answers(&text).map_err(|err| err.to_string()));
+    let key = match key {
+        Ok(key) => key,
+        Err(err) => {
+            eprintln!("error: could not load `{}`: {err}", args.answers.display());
+            process::exit(1);
+        }
+    };
+
+    let report = verify::verify(&run_quietly(args, days), &key);
+    print!("{}", verify::render_report(&report));
+    if report.iter().any(|(_, _, status)| status.is_failure()) {
+        process::exit(1);
+    }
+}
+
+fn bench_days(args: &Cli, days: &[u8], iterations: usize) {
+    let baseline = args.baseline.as_ref().map(|path| {
+        match fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| bench::read_baseline(&text))
+        {
+            Ok(baseline) => baseline,
+            Err(err) => {
+                eprintln!("error: could not load `{}`: {err}", path.display());
+                process::exit(1);
+            }
+        }
+    });
+
+    let mut results = bench::Results::new();
+    for &number in days {
+        let Some(day) = solutions::find(number) else {
+            eprintln!("day {number}: no solution registered");
+            continue;
+        };
+        let input = match args.input_source().load(number) {
+            Ok(input) => input,
+            Err(err) => {
+                eprintln!("day {number}: {err}");
+                continue;
+            }
+        };
+        match bench::bench_day(day, &input, iterations) {
+            Ok(stats) => results.extend(
+                stats
+                    .into_iter()
+                    .map(|(phase, stats)| ((number, phase), stats)),
+            ),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    print!("{}", bench::render_results(&results, baseline.as_ref()));
+    if let Some(path) = &args.save_baseline {
+        if let Err(err) = fs::write(path, bench::write_baseline(&results)) {
+            eprintln!(
+                "error: could not save baseline to `{}`: {err}",
+                path.display()
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn fetch_inputs(args: &Cli, fetch_args: &FetchArgs) {
+    let days = match (fetch_args.day, &fetch_args.days) {
+        (Some(day), _) => vec![day],
+        (None, Some(DayList(days))) => days.clone(),
+        (None, None) => vec![],
+    };
+    let fetcher = fetch::load_session(&fetch_args.session_file).and_then(|session| {
+        Ok(fetch::Fetcher {
+            client: fetch::client_for(&fetch_args.base_url)?,
+            base_url: fetch_args.base_url.clone(),
+            session,
+            input_dir: args.input_dir.clone(),
+            min_interval: fetch::DEFAULT_MIN_INTERVAL,
+        })
+    });
+    let fetcher = match fetcher {
+        Ok(fetcher) => fetcher,
+        Err(err) => {
+            eprintln!("error: {err}");
+            process::exit(1);
+        }
+    };
+
+    let mut failed = false;
+    for day in days {
+        match fetcher.fetch(day, fetch_args.force) {
+            Ok(fetch::Fetched::Downloaded(path)) => {
+                println!("day {day}: saved to `{}`", path.display())
+            }
+            Ok(fetch::Fetched::Cached(path)) => println!(
+                "day {day}: `{}` already exists, pass --force to download it again",
+                path.display()
+            ),
+            Err(err) => {
+                eprintln!("day {day}: {err}");
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn new_day(args: &Cli, new_args: &NewArgs) {
+    match scaffold::scaffold(&new_args.src_dir, &args.input_dir, new_args.day) {
+        Ok(created) => {
+            for path in created {
+                println!("created `{}`", path.display());
+            }
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+fn show_disk(disk_args: &DiskArgs) {
+    let options = ScanOptions {
+        follow_symlinks: disk_args.follow_symlinks,
+        one_file_system: disk_args.one_file_system,
+        ignore: disk_args.ignore.clone(),
+    };
+    let scan = match disk::scan(&disk_args.path, &options) {
+        Ok(scan) => scan,
+        Err(err) => {
+            eprintln!("error: {err}");
+            process::exit(1);
+        }
+    };
+    for skipped in &scan.skipped {
+        eprintln!("warning: {skipped}");
+    }
+    let root = scan.root;
+
+    // Planned before rendering, since the views that serialize the tree need an `FSNode`
+    let plan = disk_args.required.map(|required| {
+        root.plan_cleanup(&CleanupOptions {
+            capacity: disk_args.capacity.unwrap_or(root.size_below(root.root())),
+            required,
+            objective: disk_args.objective,
+            protected: disk_args
+                .protect
+                .iter()
+                .map(|path| FSPath::root().join(&FSPath::from(path.as_str())))
+                .collect(),
+        })
+    });
+    let options = RenderOptions {
+        max_depth: disk_args.max_depth,
+        human_readable: disk_args.human_readable,
+        min_size: disk_args.min_size.unwrap_or_default(),
+    };
+    match (&disk_args.query, disk_args.view) {
+        (Some(query), _) => print!("{}", root.query(query)),
+        (None, FsView::Tree) => print!("{}", root.render_tree(&options)),
+        (None, FsView::Du) => print!("{}", root.render_du(&options)),
+        (None, view) => print!("{}", view.render(&FSNode::from(root), &options)),
+    }
+    if let Some(plan) = plan {
+        match plan {
+            Ok(plan) => print!("{}", plan.explain()),
+            Err(err) => {
+                eprintln!("error: {err}");
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Reads a filesystem from a directory, or from a file in any format `--view` can save
+fn load_tree(path: &Path) -> Result<FSNode, String> {
+    if path.is_dir() {
+        return disk::scan(path, &ScanOptions::default())
+            .map(|scan| FSNode::from(scan.root))
+            .map_err(|err| err.to_string());
+    }
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("could not read `{}`: {err}", path.display()))?;
+    let tree = match text.trim_start().chars().next() {
+        Some('{') => FSNode::from_json(&text),
+        Some('$') => day_07::interpret(&text, UnseenDirectories::Create).map(|s| s.root),
+        _ => FSNode::from_lines(&text),
+    };
+    tree.map_err(|err| format!("in `{}`:\n{err}", path.display()))
+}
+
+fn show_diff(diff_args: &DiffArgs) {
+    let trees = load_tree(&diff_args.old).and_then(|old| Ok((old, load_tree(&diff_args.new)?)));
+    let (old, new) = match trees {
+        Ok(trees) => trees,
+        Err(err) => {
+            eprintln!("error: {err}");
+            process::exit(1);
+        }
+    };
+    let diff = old.diff(&new);
+    print!("{}", diff.render());
+    // Like `diff`, exit with 1 when the trees differ
+    if !diff.is_empty() {
+        process::exit(1);
     }
 }
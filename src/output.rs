@@ -0,0 +1,189 @@
+use super::answer::{Answer, Answers};
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Tsv,
+}
+
+pub fn render(format: OutputFormat, results: &[(u8, Answers)]) -> String {
+    match format {
+        OutputFormat::Text => render_text(results),
+        OutputFormat::Json => render_json(results),
+        OutputFormat::Tsv => render_tsv(results),
+    }
+}
+
+fn parts(answers: &Answers) -> [(u8, &Answer); 2] {
+    [(1, &answers.part1), (2, &answers.part2)]
+}
+
+fn render_text(results: &[(u8, Answers)]) -> String {
+    let mut output = String::new();
+    for (day, answers) in results {
+        output.push_str(&format!("Day {day}\n"));
+        for (part, answer) in parts(answers) {
+            match answer {
+                Answer::Picture(_) => output.push_str(&format!("Part {part}:\n{answer}\n")),
+                _ => output.push_str(&format!("Part {part}: {answer}\n")),
+            }
+        }
+    }
+    output
+}
+
+/// One JSON object per day and line, so a single day is a valid JSON document on its own
+fn render_json(results: &[(u8, Answers)]) -> String {
+    results
+        .iter()
+        .map(|(day, answers)| {
+            format!(
+                "{{\"day\":{day},\"part1\":{},\"part2\":{}}}\n",
+                json_value(&answers.part1),
+                json_value(&answers.part2)
+            )
+        })
+        .collect()
+}
+
+pub fn json_value(answer: &Answer) -> String {
+    match answer {
+        Answer::Number(number) => number.to_string(),
+        Answer::Text(text) => json_string(text),
+        Answer::Picture(rows) => {
+            let rows: Vec<String> = rows.iter().map(|row| json_string(row)).collect();
+            format!("[{}]", rows.join(","))
+        }
+    }
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut result = String::from('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ch if ch.is_control() => result.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn render_tsv(results: &[(u8, Answers)]) -> String {
+    let mut output = String::from("day\tpart\tanswer\n");
+    for (day, answers) in results {
+        for (part, answer) in parts(answers) {
+            output.push_str(&format!("{day}\t{part}\t{}\n", tsv_field(answer)));
+        }
+    }
+    output
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Renders rows as space-separated, aligned columns below a header.
+/// Rows may be shorter than the header, missing cells are left blank.
+pub fn render_table(header: &[(&str, Align)], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header_row: Vec<String> = header.iter().map(|(name, _)| String::from(*name)).collect();
+    let mut output = String::new();
+    for row in std::iter::once(&header_row).chain(rows) {
+        let cells: Vec<String> = header
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(idx, ((_, align), &width))| {
+                let cell = row.get(idx).map_or("", String::as_str);
+                match align {
+                    Align::Left => format!("{cell:<width$}"),
+                    Align::Right => format!("{cell:>width$}"),
+                }
+            })
+            .collect();
+        output.push_str(cells.join("  ").trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+pub fn tsv_field(answer: &Answer) -> String {
+    answer
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn results() -> Vec<(u8, Answers)> {
+        vec![
+            (
+                5,
+                Answers {
+                    part1: Answer::Text(String::from("CMZ")),
+                    part2: Answer::Number(42),
+                },
+            ),
+            (
+                10,
+                Answers {
+                    part1: Answer::Number(13140),
+                    part2: Answer::Picture(vec![String::from("#."), String::from(".#")]),
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            render(OutputFormat::Text, &results()),
+            "Day 5\nPart 1: CMZ\nPart 2: 42\nDay 10\nPart 1: 13140\nPart 2:\n#.\n.#\n"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        assert_eq!(
+            render(OutputFormat::Json, &results()),
+            "{\"day\":5,\"part1\":\"CMZ\",\"part2\":42}\n{\"day\":10,\"part1\":13140,\"part2\":[\"#.\",\".#\"]}\n"
+        );
+    }
+
+    #[test]
+    fn test_render_tsv() {
+        assert_eq!(
+            render(OutputFormat::Tsv, &results()),
+            "day\tpart\tanswer\n5\t1\tCMZ\n5\t2\t42\n10\t1\t13140\n10\t2\t#.\\n.#\n"
+        );
+    }
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\nd"), "\"a\\\"b\\\\c\\nd\"");
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An error in a puzzle input, pointing at the offending text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: Option<u8>,
+    pub message: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// Number of characters to highlight, starting at `column`
+    pub len: usize,
+    /// The full text of the offending line
+    pub text: String,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, line: usize, text: &str) -> ParseError {
+        ParseError {
+            day: None,
+            message: message.into(),
+            line,
+            column: 1,
+            len: text.chars().count().max(1),
+            text: String::from(text),
+        }
+    }
+
+    /// Creates an error highlighting `span`, which should be a slice of `text`.
+    /// Falls back to highlighting the whole line if it is not.
+    pub fn at(message: impl Into<String>, line: usize, text: &str, span: &str) -> ParseError {
+        let error = ParseError::new(message, line, text);
+        let start = (span.as_ptr() as usize).wrapping_sub(text.as_ptr() as usize);
+        if start > text.len() || start + span.len() > text.len() {
+            return error;
+        }
+
+        ParseError {
+            column: text[..start].chars().count() + 1,
+            len: span.chars().count().max(1),
+            ..error
+        }
+    }
+
+    pub fn with_day(self, day: u8) -> ParseError {
+        ParseError {
+            day: Some(day),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = match self.day {
+            Some(day) => format!("day {day}, line {}, column {}", self.line, self.column),
+            None => format!("line {}, column {}", self.line, self.column),
+        };
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{gutter}--> {location}")?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.text)?;
+        write!(
+            f,
+            "{gutter} | {}{}",
+            " ".repeat(self.column - 1),
+            "^".repeat(self.len)
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Iterates over the lines of `input` together with their 1-based line numbers
+pub fn numbered_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.lines().enumerate().map(|(idx, line)| (idx + 1, line))
+}
+
+/// Parses `span`, a slice of the line `text`, as a number
+pub fn parse_number<T: FromStr>(line: usize, text: &str, span: &str) -> Result<T, ParseError> {
+    span.parse()
+        .map_err(|_| ParseError::at(format!("invalid number `{span}`"), line, text, span))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_error_at_span() {
+        let text = "move 1 from x to 3";
+        let error = ParseError::at("invalid number", 4, text, &text[12..13]);
+        assert_eq!((error.line, error.column, error.len), (4, 13, 1));
+    }
+
+    #[test]
+    fn test_error_span_outside_line() {
+        let error = ParseError::at("invalid", 1, "abc", "xyz");
+        assert_eq!((error.column, error.len), (1, 3));
+    }
+
+    #[test]
+    fn test_display() {
+        let text = "A Q";
+        let error = ParseError::at("unsupported hand `Q`", 12, text, &text[2..]).with_day(2);
+        assert_eq!(
+            error.to_string(),
+            "error: unsupported hand `Q`
+  --> day 2, line 12, column 3
+   |
+12 | A Q
+   |   ^"
+        );
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let text = "12-x4";
+        assert_eq!(parse_number::<u32>(1, text, &text[..2]), Ok(12));
+        assert_eq!(
+            parse_number::<u32>(1, text, &text[3..]).unwrap_err().column,
+            4
+        );
+    }
+}
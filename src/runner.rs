@@ -0,0 +1,215 @@
+use super::answer::Answer;
+use super::input::{InputError, InputSource};
+use super::output::{render_table, Align};
+use super::parse::ParseError;
+use super::solutions::{self, Run};
+use std::any::Any;
+use std::panic;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum Outcome {
+    Solved(Run),
+    NoInput(InputError),
+    Invalid(ParseError),
+    Failed(String),
+}
+
+/// A list of days such as `3,5,7-9`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayList(pub Vec<u8>);
+
+impl FromStr for DayList {
+    type Err = String;
+
+    fn from_str(list: &str) -> Result<DayList, String> {
+        parse_day_list(list).map(DayList)
+    }
+}
+
+pub fn parse_day_list(list: &str) -> Result<Vec<u8>, String> {
+    let mut days = vec![];
+    for item in list
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let parse_day = |day: &str| {
+            day.trim()
+                .parse::<u8>()
+                .map_err(|_| format!("`{day}` is not a valid day"))
+        };
+        match item.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_day(start)?, parse_day(end)?);
+                if start > end {
+                    return Err(format!("range `{item}` is empty"));
+                }
+                days.extend(start..=end);
+            }
+            None => days.push(parse_day(item)?),
+        }
+    }
+    days.sort_unstable();
+    days.dedup();
+    Ok(days)
+}
+
+/// Runs each of the given days, catching panics so one broken day doesn't stop the others.
+/// The panic hook is left alone, callers that only want the summary can silence it.
+pub fn run_days(days: &[u8], source: &InputSource) -> Vec<(u8, Outcome)> {
+    days.iter()
+        .map(|&number| {
+            let outcome = match solutions::find(number) {
+                None => Outcome::Failed(String::from("no solution registered")),
+                Some(day) => match source.load(number) {
+                    Err(err) => Outcome::NoInput(err),
+                    Ok(input) => match panic::catch_unwind(|| day.run(&input)) {
+                        Ok(Ok(run)) => Outcome::Solved(run),
+                        Ok(Err(err)) => Outcome::Invalid(err),
+                        Err(payload) => Outcome::Failed(panic_message(payload)),
+                    },
+                },
+            };
+            (number, outcome)
+        })
+        .collect()
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("panicked: {message}")
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("panicked: {message}")
+    } else {
+        String::from("panicked")
+    }
+}
+
+fn single_line(answer: &Answer) -> String {
+    match answer {
+        Answer::Picture(rows) => format!(
+            "<{}x{} picture>",
+            rows.len(),
+            rows.first().map_or(0, |row| row.chars().count())
+        ),
+        _ => answer.to_string(),
+    }
+}
+
+pub fn render_summary(outcomes: &[(u8, Outcome)]) -> String {
+    let mut rows: Vec<Vec<String>> = vec![];
+    for (day, outcome) in outcomes {
+        match outcome {
+            Outcome::Solved(run) => {
+                let parts = [
+                    (&run.answers.part1, run.timings.part_one),
+                    (&run.answers.part2, run.timings.part_two),
+                ];
+                for (idx, (answer, solve_time)) in parts.iter().enumerate() {
+                    rows.push(vec![
+                        day.to_string(),
+                        (idx + 1).to_string(),
+                        single_line(answer),
+                        format!("{:.1?}", run.timings.parse),
+                        format!("{solve_time:.1?}"),
+                    ]);
+                }
+            }
+            Outcome::NoInput(_) => rows.push(status_row(*day, "no input")),
+            Outcome::Invalid(_) => rows.push(status_row(*day, "invalid input")),
+            Outcome::Failed(_) => rows.push(status_row(*day, "FAILED")),
+        }
+    }
+
+    let mut output = render_table(
+        &[
+            ("Day", Align::Right),
+            ("Part", Align::Right),
+            ("Answer", Align::Left),
+            ("Parse", Align::Right),
+            ("Solve", Align::Right),
+        ],
+        &rows,
+    );
+
+    let problems = problems(outcomes);
+    if !problems.is_empty() {
+        output.push('\n');
+        for problem in problems {
+            output.push_str(&format!("{problem}\n"));
+        }
+    }
+
+    output
+}
+
+/// Describes every day that failed or had no input, one line each
+pub fn problems(outcomes: &[(u8, Outcome)]) -> Vec<String> {
+    outcomes
+        .iter()
+        .filter_map(|(day, outcome)| match outcome {
+            Outcome::Solved(_) => None,
+            Outcome::NoInput(err) => Some(format!("day {day}: {err}")),
+            Outcome::Invalid(err) => Some(err.to_string()),
+            Outcome::Failed(message) => Some(format!("day {day}: {message}")),
+        })
+        .collect()
+}
+
+fn status_row(day: u8, status: &str) -> Vec<String> {
+    vec![day.to_string(), String::from("-"), String::from(status)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::answer::Answers;
+    use super::super::solutions::Timings;
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_day_list() {
+        assert_eq!(parse_day_list("3,5,7-9"), Ok(vec![3, 5, 7, 8, 9]));
+        assert_eq!(parse_day_list("9, 1-2,2"), Ok(vec![1, 2, 9]));
+        assert!(parse_day_list("3-1").is_err());
+        assert!(parse_day_list("x").is_err());
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let run = Run {
+            answers: Answers {
+                part1: Answer::Number(24000),
+                part2: Answer::Number(45000),
+            },
+            timings: Timings {
+                parse: Duration::from_micros(12),
+                part_one: Duration::from_micros(3),
+                part_two: Duration::from_micros(4),
+            },
+        };
+        let outcomes = vec![
+            (1, Outcome::Solved(run)),
+            (
+                2,
+                Outcome::NoInput(InputError::Missing(PathBuf::from("inputs/day02.txt"))),
+            ),
+            (3, Outcome::Failed(String::from("panicked: oops"))),
+        ];
+        assert_eq!(
+            render_summary(&outcomes),
+            "Day  Part  Answer     Parse  Solve
+  1     1  24000     12.0µs  3.0µs
+  1     2  45000     12.0µs  4.0µs
+  2     -  no input
+  3     -  FAILED
+
+day 2: no input found at `inputs/day02.txt` (pass --input <path> or --stdin to read it from elsewhere)
+day 3: panicked: oops
+"
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use super::input::default_input_path;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_SRC_DIR: &str = "src";
+
+#[derive(Debug)]
+pub enum ScaffoldError {
+    InvalidDay(u8),
+    /// The module file already exists, or the day is already registered
+    Exists(String),
+    /// `solutions.rs` does not have the shape the generator expects
+    Registry(String),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScaffoldError::InvalidDay(day) => write!(f, "day {day} is not between 1 and 25"),
+            ScaffoldError::Exists(what) => write!(f, "{what} already exists, not touching it"),
+            ScaffoldError::Registry(message) => write!(f, "could not register the day: {message}"),
+            ScaffoldError::Io(path, err) => {
+                write!(f, "could not access `{}`: {err}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScaffoldError {}
+
+const TEMPLATE: &str = r#"use super::super::parse::ParseError;
+use super::Solution;
+
+pub fn parse_input(input: &str) -> Result<Vec<&str>, ParseError> {
+    Ok(input.lines().map(str::trim).collect())
+}
+
+pub struct Day{NN};
+
+impl Solution for Day{NN} {
+    type Parsed<'a> = Vec<&'a str>;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        parse_input(input)
+    }
+
+    fn part_one(_lines: &Self::Parsed<'_>) -> usize {
+        0
+    }
+
+    fn part_two(_lines: &Self::Parsed<'_>) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SAMPLE: &str = "";
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(Day{NN}::part_one(&parse_input(SAMPLE).unwrap()), 0);
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(Day{NN}::part_two(&parse_input(SAMPLE).unwrap()), 0);
+    }
+}
+"#;
+
+/// Source of a new, placeholder day module
+pub fn module_source(day: u8) -> String {
+    TEMPLATE.replace("{NN}", &format!("{day:02}"))
+}
+
+/// Inserts `line` in sorted position among the lines starting with `prefix`, after the last one
+/// that sorts before it
+fn insert_sorted(source: &str, prefix: &str, line: &str) -> Result<String, ScaffoldError> {
+    let lines: Vec<&str> = source.lines().collect();
+    let matching: Vec<usize> = (0..lines.len())
+        .filter(|&idx| lines[idx].starts_with(prefix))
+        .collect();
+    let Some(&last) = matching.last() else {
+        return Err(ScaffoldError::Registry(format!(
+            "no line starting with `{}` found",
+            prefix.trim()
+        )));
+    };
+    let position = matching
+        .iter()
+        .find(|&&idx| lines[idx] > line)
+        .copied()
+        .unwrap_or(last + 1);
+
+    let mut result: Vec<&str> = lines;
+    result.insert(position, line);
+    Ok(result.join("\n") + "\n")
+}
+
+/// Adds the `mod` declaration and the `DAYS` entry for `day` to the source of `solutions.rs`
+pub fn register(solutions: &str, day: u8) -> Result<String, ScaffoldError> {
+    let declaration = format!("pub mod day_{day:02};");
+    let entry = format!("    Day::new::<day_{day:02}::Day{day:02}>({day}),");
+    if solutions.lines().any(|line| line == declaration) {
+        return Err(ScaffoldError::Exists(format!(
+            "`{declaration}` in solutions.rs"
+        )));
+    }
+
+    let solutions = insert_sorted(solutions, "pub mod day_", &declaration)?;
+    insert_sorted(&solutions, "    Day::new::<day_", &entry)
+}
+
+/// Generates the module for `day` below `src_dir`, registers it and creates an empty input file.
+/// Nothing is written if the day already exists. Returns the created files.
+pub fn scaffold(src_dir: &Path, input_dir: &Path, day: u8) -> Result<Vec<PathBuf>, ScaffoldError> {
+    if !(1..=25).contains(&day) {
+        return Err(ScaffoldError::InvalidDay(day));
+    }
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |err| ScaffoldError::Io(path, err)
+    };
+
+    let module = src_dir.join("solutions").join(format!("day_{day:02}.rs"));
+    if module.exists() {
+        return Err(ScaffoldError::Exists(format!("`{}`", module.display())));
+    }
+    let registry = src_dir.join("solutions.rs");
+    let solutions = fs::read_to_string(&registry).map_err(io_error(&registry))?;
+    let solutions = register(&solutions, day)?;
+
+    fs::write(&module, module_source(day)).map_err(io_error(&module))?;
+    fs::write(&registry, solutions).map_err(io_error(&registry))?;
+    let mut created = vec![module];
+
+    // An input that was already fetched is left alone
+    let input = default_input_path(input_dir, day);
+    if !input.exists() {
+        fs::create_dir_all(input_dir).map_err(io_error(input_dir))?;
+        fs::write(&input, "").map_err(io_error(&input))?;
+        created.push(input);
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SOLUTIONS: &str = "use super::answer::Answer;
+
+pub mod day_01;
+pub mod day_03;
+
+pub const DAYS: &[Day] = &[
+    Day::new::<day_01::Day01>(1),
+    Day::new::<day_03::Day03>(3),
+];
+";
+
+    #[test]
+    fn test_register() {
+        assert_eq!(
+            register(SOLUTIONS, 2).unwrap(),
+            "use super::answer::Answer;
+
+pub mod day_01;
+pub mod day_02;
+pub mod day_03;
+
+pub const DAYS: &[Day] = &[
+    Day::new::<day_01::Day01>(1),
+    Day::new::<day_02::Day02>(2),
+    Day::new::<day_03::Day03>(3),
+];
+"
+        );
+        assert!(register(SOLUTIONS, 11)
+            .unwrap()
+            .contains("pub mod day_03;\npub mod day_11;\n"));
+    }
+
+    #[test]
+    fn test_register_existing_day() {
+        assert!(matches!(
+            register(SOLUTIONS, 3),
+            Err(ScaffoldError::Exists(_))
+        ));
+    }
+
+    #[test]
+    fn test_module_source() {
+        let source = module_source(7);
+        assert!(source.contains("pub struct Day07;"));
+        assert!(source.contains("impl Solution for Day07 {"));
+        assert!(!source.contains("{NN}"));
+    }
+}
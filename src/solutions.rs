@@ -1,3 +1,7 @@
+use super::answer::{Answer, Answers};
+use super::parse::ParseError;
+use std::time::{Duration, Instant};
+
 pub mod day_01;
 pub mod day_02;
 pub mod day_03;
@@ -8,3 +12,90 @@ pub mod day_07;
 pub mod day_08;
 pub mod day_09;
 pub mod day_10;
+
+pub trait Solution {
+    /// Parsed representation of the puzzle input, shared by both parts
+    type Parsed<'a>;
+    type PartOne: Into<Answer>;
+    type PartTwo: Into<Answer>;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError>;
+    fn part_one(parsed: &Self::Parsed<'_>) -> Self::PartOne;
+    fn part_two(parsed: &Self::Parsed<'_>) -> Self::PartTwo;
+}
+
+/// A registered day, with the concrete `Solution` erased so days can be stored side by side
+pub struct Day {
+    pub number: u8,
+    run: fn(&str) -> Result<Run, ParseError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part_one: Duration,
+    pub part_two: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub answers: Answers,
+    pub timings: Timings,
+}
+
+impl Day {
+    const fn new<S: Solution>(number: u8) -> Day {
+        Day {
+            number,
+            run: run::<S>,
+        }
+    }
+
+    pub fn run(&self, input: &str) -> Result<Run, ParseError> {
+        (self.run)(input).map_err(|err| err.with_day(self.number))
+    }
+}
+
+fn run<S: Solution>(input: &str) -> Result<Run, ParseError> {
+    let start = Instant::now();
+    let parsed = S::parse(input)?;
+    let parse = start.elapsed();
+
+    let start = Instant::now();
+    let part1 = S::part_one(&parsed).into();
+    let part_one = start.elapsed();
+
+    let start = Instant::now();
+    let part2 = S::part_two(&parsed).into();
+    let part_two = start.elapsed();
+
+    Ok(Run {
+        answers: Answers { part1, part2 },
+        timings: Timings {
+            parse,
+            part_one,
+            part_two,
+        },
+    })
+}
+
+pub const DAYS: &[Day] = &[
+    Day::new::<day_01::Day01>(1),
+    Day::new::<day_02::Day02>(2),
+    Day::new::<day_03::Day03>(3),
+    Day::new::<day_04::Day04>(4),
+    Day::new::<day_05::Day05>(5),
+    Day::new::<day_06::Day06>(6),
+    Day::new::<day_07::Day07>(7),
+    Day::new::<day_08::Day08>(8),
+    Day::new::<day_09::Day09>(9),
+    Day::new::<day_10::Day10>(10),
+];
+
+pub fn find(number: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
+
+pub fn available() -> Vec<u8> {
+    DAYS.iter().map(|day| day.number).collect()
+}
@@ -1,32 +1,76 @@
-const INPUT: &str = include_str!("day_01.txt");
+use super::super::parse::{numbered_lines, parse_number, ParseError};
+use super::Solution;
 
-fn convert_input_to_array(input: &str) -> Vec<Vec<i32>> {
-    input
-        .lines()
-        .fold(vec![vec![]], |mut accum: Vec<Vec<i32>>, line: &str| {
+fn convert_input_to_array(input: &str) -> Result<Vec<Vec<i32>>, ParseError> {
+    numbered_lines(input).try_fold(
+        vec![vec![]],
+        |mut accum: Vec<Vec<i32>>, (line_number, line)| {
             if line.is_empty() {
                 accum.push(vec![]);
             } else {
                 let last = accum.last_mut().unwrap();
-                let current_value: i32 = line.parse().unwrap();
+                let current_value: i32 = parse_number(line_number, line, line.trim())?;
                 last.push(current_value);
             }
-            accum
-        })
+            Ok(accum)
+        },
+    )
 }
 
-pub fn print_solution() {
-    let result = convert_input_to_array(INPUT);
-    let mut sums: Vec<i32> = result
-        .iter()
-        .map(|carrying| carrying.iter().sum())
-        .collect();
-    let single_max = sums
-        .iter()
-        .reduce(|max, i| if i > max { i } else { max })
-        .unwrap();
-    println!("Max: {:?}", single_max);
-    sums.sort_unstable_by(|a, b| b.cmp(a));
-    let highest_three = sums.as_slice().get(0..3).unwrap();
-    println!("Max 3: {:?}", highest_three.iter().sum::<i32>())
+fn elf_totals(elves: &[Vec<i32>]) -> Vec<i32> {
+    elves.iter().map(|carrying| carrying.iter().sum()).collect()
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    type Parsed<'a> = Vec<Vec<i32>>;
+    type PartOne = i32;
+    type PartTwo = i32;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        let elves = convert_input_to_array(input)?;
+        let carrying = elves.iter().filter(|carrying| !carrying.is_empty()).count();
+        if carrying < 3 {
+            let (line_number, line) = numbered_lines(input).last().unwrap_or((1, ""));
+            return Err(ParseError::new(
+                format!("the top three elves are needed, but only {carrying} carry food"),
+                line_number,
+                line,
+            ));
+        }
+        Ok(elves)
+    }
+
+    fn part_one(elves: &Self::Parsed<'_>) -> i32 {
+        elf_totals(elves)
+            .into_iter()
+            .reduce(|max, i| if i > max { i } else { max })
+            .unwrap()
+    }
+
+    fn part_two(elves: &Self::Parsed<'_>) -> i32 {
+        let mut sums = elf_totals(elves);
+        sums.sort_unstable_by(|a, b| b.cmp(a));
+        let highest_three = sums.as_slice().get(0..3).unwrap();
+        highest_three.iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_errors() {
+        let error = Day01::parse("1000\n\n2000\n").unwrap_err();
+        assert_eq!(
+            error.message,
+            "the top three elves are needed, but only 2 carry food"
+        );
+        assert_eq!(error.line, 3);
+        assert_eq!(Day01::parse("").unwrap_err().line, 1);
+        assert_eq!(Day01::parse("1\n\n2\n\n3").map(|elves| elves.len()), Ok(3));
+    }
 }
@@ -1,7 +1,8 @@
-const INPUT: &str = include_str!("day_02.txt");
+use super::super::parse::{numbered_lines, ParseError};
+use super::Solution;
 
 #[derive(Debug, Clone, Copy)]
-enum Hand {
+pub enum Hand {
     Rock,
     Paper,
     Scissors,
@@ -35,15 +36,15 @@ impl Hand {
             .unwrap()
     }
 
-    fn from_char(ch: &str) -> Hand {
+    fn from_char(ch: &str) -> Option<Hand> {
         match ch {
-            "A" => Self::Rock,
-            "B" => Self::Paper,
-            "C" => Self::Scissors,
-            "X" => Self::Rock,
-            "Y" => Self::Paper,
-            "Z" => Self::Scissors,
-            _ => panic!("Unsupported char"),
+            "A" => Some(Self::Rock),
+            "B" => Some(Self::Paper),
+            "C" => Some(Self::Scissors),
+            "X" => Some(Self::Rock),
+            "Y" => Some(Self::Paper),
+            "Z" => Some(Self::Scissors),
+            _ => None,
         }
     }
 
@@ -56,8 +57,8 @@ impl Hand {
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum Outcome {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
     Lose,
     Win,
     Draw,
@@ -72,36 +73,64 @@ impl Outcome {
         }
     }
 
-    fn from_char(ch: &str) -> Outcome {
+    fn from_char(ch: &str) -> Option<Outcome> {
         match ch {
-            "X" => Outcome::Lose,
-            "Y" => Self::Draw,
-            "Z" => Self::Win,
-            _ => panic!("Unsupported char!"),
+            "X" => Some(Outcome::Lose),
+            "Y" => Some(Self::Draw),
+            "Z" => Some(Self::Win),
+            _ => None,
         }
     }
 }
 
-fn convert_to_hands(input: &str) -> Vec<Vec<Hand>> {
-    input
-        .lines()
-        .map(|line| line.split(' ').map(Hand::from_char).collect())
+/// Splits a line into its two columns, e.g. `A Y`
+fn split_round(line_number: usize, line: &str) -> Result<(&str, &str), ParseError> {
+    let parts: Vec<&str> = line.split(' ').collect();
+    match parts.as_slice() {
+        [lhs, rhs] => Ok((lhs, rhs)),
+        _ => Err(ParseError::new(
+            "expected two columns separated by a space, e.g. `A Y`",
+            line_number,
+            line,
+        )),
+    }
+}
+
+fn parse_hand(line_number: usize, line: &str, ch: &str) -> Result<Hand, ParseError> {
+    Hand::from_char(ch)
+        .ok_or_else(|| ParseError::at(format!("unsupported hand `{ch}`"), line_number, line, ch))
+}
+
+fn convert_to_hands(input: &str) -> Result<Vec<Vec<Hand>>, ParseError> {
+    numbered_lines(input)
+        .map(|(line_number, line)| {
+            let (lhs, rhs) = split_round(line_number, line)?;
+            Ok(vec![
+                parse_hand(line_number, line, lhs)?,
+                parse_hand(line_number, line, rhs)?,
+            ])
+        })
         .collect()
 }
 
-fn convert_to_hand_and_outcome(input: &str) -> Vec<(Hand, Outcome)> {
-    input
-        .lines()
-        .map(|line| {
-            let parts: Vec<&str> = line.split(' ').collect();
-            let hand = parts.first().unwrap();
-            let outcome = parts.last().unwrap();
-            (Hand::from_char(hand), Outcome::from_char(outcome))
+fn convert_to_hand_and_outcome(input: &str) -> Result<Vec<(Hand, Outcome)>, ParseError> {
+    numbered_lines(input)
+        .map(|(line_number, line)| {
+            let (hand, outcome) = split_round(line_number, line)?;
+            let outcome = Outcome::from_char(outcome).ok_or_else(|| {
+                ParseError::at(
+                    format!("unsupported outcome `{outcome}`"),
+                    line_number,
+                    line,
+                    outcome,
+                )
+            })?;
+            Ok((parse_hand(line_number, line, hand)?, outcome))
         })
         .collect()
 }
 
-fn score_hands(hands: Vec<Vec<Hand>>) -> u32 {
+fn score_hands(hands: &[Vec<Hand>]) -> u32 {
     hands
         .iter()
         .map(|hands| {
@@ -112,7 +141,7 @@ fn score_hands(hands: Vec<Vec<Hand>>) -> u32 {
         .sum()
 }
 
-fn find_hands_to_reach_outcome(input: Vec<(Hand, Outcome)>) -> Vec<Vec<Hand>> {
+fn find_hands_to_reach_outcome(input: &[(Hand, Outcome)]) -> Vec<Vec<Hand>> {
     input
         .iter()
         .map(|(hand, outcome)| {
@@ -122,16 +151,27 @@ fn find_hands_to_reach_outcome(input: Vec<(Hand, Outcome)>) -> Vec<Vec<Hand>> {
         .collect()
 }
 
-pub fn print_solution() {
-    let hands_map = convert_to_hands(INPUT);
-    let total_score: u32 = score_hands(hands_map);
-    println!("Total score first part: {:?}", total_score);
+pub struct Day02;
+
+impl Solution for Day02 {
+    type Parsed<'a> = (Vec<Vec<Hand>>, Vec<(Hand, Outcome)>);
+    type PartOne = u32;
+    type PartTwo = u32;
 
-    let hands_and_desired_outcomes = convert_to_hand_and_outcome(INPUT);
-    let hands_from_outcomes: Vec<Vec<Hand>> =
-        find_hands_to_reach_outcome(hands_and_desired_outcomes);
-    let total_score_outcomes = score_hands(hands_from_outcomes);
-    println!("Total score second part: {}", total_score_outcomes)
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Ok((
+            convert_to_hands(input)?,
+            convert_to_hand_and_outcome(input)?,
+        ))
+    }
+
+    fn part_one((hands, _): &Self::Parsed<'_>) -> u32 {
+        score_hands(hands)
+    }
+
+    fn part_two((_, hands_and_desired_outcomes): &Self::Parsed<'_>) -> u32 {
+        score_hands(&find_hands_to_reach_outcome(hands_and_desired_outcomes))
+    }
 }
 
 #[cfg(test)]
@@ -146,16 +186,23 @@ C Z
 
     #[test]
     fn test_first_part_output() {
-        assert_eq!(score_hands(convert_to_hands(SAMPLE)), 15)
+        assert_eq!(score_hands(&convert_to_hands(SAMPLE).unwrap()), 15)
     }
 
     #[test]
     fn test_second_part_output() {
         assert_eq!(
-            score_hands(find_hands_to_reach_outcome(convert_to_hand_and_outcome(
-                SAMPLE
-            ))),
+            score_hands(&find_hands_to_reach_outcome(
+                &convert_to_hand_and_outcome(SAMPLE).unwrap()
+            )),
             12
         )
     }
+
+    #[test]
+    fn test_invalid_hand() {
+        let error = convert_to_hands("A Y\nB Q\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(error.message, "unsupported hand `Q`");
+    }
 }
@@ -1,7 +1,7 @@
+use super::super::parse::{numbered_lines, ParseError};
+use super::Solution;
 use std::collections::HashSet;
 
-const INPUT: &str = include_str!("day_03.txt");
-
 fn split_compartments(input: &str) -> Vec<[&str; 2]> {
     input
         .lines()
@@ -64,16 +64,93 @@ fn find_common_letter(parts: &[&str]) -> char {
     *intersection.iter().last().unwrap()
 }
 
-pub fn print_solution() {
-    let sum = priority_sum(INPUT);
-    println!("Sum: {sum}");
+/// Checks that every rucksack holds an even number of items, with one in both compartments, and
+/// that they can be grouped by three with an item common to each group
+fn parse_input(input: &str) -> Result<&str, ParseError> {
+    let mut rucksacks = 0;
+    let mut last_line = (0, "");
+    let mut group = vec![];
+    for (line_number, line) in numbered_lines(input) {
+        if let Some((idx, item)) = line
+            .char_indices()
+            .find(|(_, ch)| !ch.is_ascii_alphabetic())
+        {
+            return Err(ParseError::at(
+                format!("unsupported item `{item}`"),
+                line_number,
+                line,
+                &line[idx..idx + item.len_utf8()],
+            ));
+        }
+        if line.len() % 2 != 0 {
+            return Err(ParseError::new(
+                format!(
+                    "rucksack has an odd number of items ({}) and can't be split into compartments",
+                    line.len()
+                ),
+                line_number,
+                line,
+            ));
+        }
+        let (first, second) = line.split_at(line.len() / 2);
+        if !first.chars().any(|item| second.contains(item)) {
+            return Err(ParseError::new(
+                "no item is in both compartments",
+                line_number,
+                line,
+            ));
+        }
+        group.push(line);
+        if let [a, b, c] = group[..] {
+            if !a.chars().any(|item| b.contains(item) && c.contains(item)) {
+                return Err(ParseError::new(
+                    format!(
+                        "no item is in all three rucksacks of the group starting on line {}",
+                        line_number - 2
+                    ),
+                    line_number,
+                    line,
+                ));
+            }
+            group.clear();
+        }
+        rucksacks += 1;
+        last_line = (line_number, line);
+    }
 
-    let chars: u32 = split_threes(INPUT)
-        .iter()
-        .map(|parts| char_value(find_common_letter(parts)))
-        .sum();
+    if rucksacks % 3 != 0 {
+        let (line_number, line) = last_line;
+        return Err(ParseError::new(
+            format!("{rucksacks} rucksacks can't be split into groups of three"),
+            line_number,
+            line,
+        ));
+    }
+
+    Ok(input)
+}
+
+pub struct Day03;
 
-    println!("Chars: {chars:?}")
+impl Solution for Day03 {
+    type Parsed<'a> = &'a str;
+    type PartOne = u32;
+    type PartTwo = u32;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        parse_input(input)
+    }
+
+    fn part_one(input: &Self::Parsed<'_>) -> u32 {
+        priority_sum(input)
+    }
+
+    fn part_two(input: &Self::Parsed<'_>) -> u32 {
+        split_threes(input)
+            .iter()
+            .map(|parts| char_value(find_common_letter(parts)))
+            .sum()
+    }
 }
 
 #[cfg(test)]
@@ -161,4 +238,33 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
             'Z'
         );
     }
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(parse_input(SAMPLE), Ok(SAMPLE));
+
+        let error = parse_input("abca\nab1d\nabca").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+
+        let error = parse_input("abca\nabcd").unwrap_err();
+        assert_eq!(
+            (error.line, error.message.as_str()),
+            (2, "no item is in both compartments")
+        );
+
+        let error = parse_input("abca\nabca\nxyzx").unwrap_err();
+        assert_eq!(
+            (error.line, error.message.as_str()),
+            (
+                3,
+                "no item is in all three rucksacks of the group starting on line 1"
+            )
+        );
+
+        let error = parse_input("abca\nabca").unwrap_err();
+        assert_eq!(
+            error.message,
+            "2 rucksacks can't be split into groups of three"
+        );
+    }
 }
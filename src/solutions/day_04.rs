@@ -1,7 +1,8 @@
-const INPUT: &str = include_str!("day_04.txt");
+use super::super::parse::{numbered_lines, parse_number, ParseError};
+use super::Solution;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Range {
+pub struct Range {
     start: u32,
     end: u32,
 }
@@ -27,44 +28,71 @@ impl Range {
     }
 }
 
-fn parse_input(input: &str) -> Vec<(Range, Range)> {
-    input
-        .trim()
-        .lines()
-        .map(|line| {
-            let mut ranges = line.trim().split(',').map(|assignment| {
-                let mut parts = assignment.split('-');
-                let start: u32 = parts.next().unwrap().parse().unwrap();
-                let end: u32 = parts.next().unwrap().parse().unwrap();
-                Range::new(start..end)
-            });
-            let lhs = ranges.next().unwrap();
-            let rhs = ranges.next().unwrap();
-            (lhs, rhs)
+fn parse_range(line_number: usize, line: &str, assignment: &str) -> Result<Range, ParseError> {
+    let Some((start, end)) = assignment.split_once('-') else {
+        return Err(ParseError::at(
+            format!("expected a range like `2-4`, got `{assignment}`"),
+            line_number,
+            line,
+            assignment,
+        ));
+    };
+    let start: u32 = parse_number(line_number, line, start)?;
+    let end: u32 = parse_number(line_number, line, end)?;
+    Ok(Range::new(start..end))
+}
+
+fn parse_input(input: &str) -> Result<Vec<(Range, Range)>, ParseError> {
+    numbered_lines(input)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            let Some((lhs, rhs)) = line.trim().split_once(',') else {
+                return Err(ParseError::new(
+                    "expected two assignments separated by `,`",
+                    line_number,
+                    line,
+                ));
+            };
+            Ok((
+                parse_range(line_number, line, lhs)?,
+                parse_range(line_number, line, rhs)?,
+            ))
         })
         .collect()
 }
 
-fn count_fully_contained_pairs(pairs: Vec<(Range, Range)>) -> usize {
+fn count_fully_contained_pairs(pairs: &[(Range, Range)]) -> usize {
     pairs
         .iter()
         .filter(|(lhs, rhs)| lhs.fully_contains(rhs) || rhs.fully_contains(lhs))
         .count()
 }
 
-fn count_overlaps(pairs: Vec<(Range, Range)>) -> usize {
+fn count_overlaps(pairs: &[(Range, Range)]) -> usize {
     pairs
         .iter()
         .filter(|(lhs, rhs)| lhs.overlaps(rhs) || rhs.overlaps(lhs))
         .count()
 }
 
-pub fn print_solution() {
-    let number_of_double_shifts = count_fully_contained_pairs(parse_input(INPUT));
-    println!("Number of double shifts: {number_of_double_shifts}");
+pub struct Day04;
+
+impl Solution for Day04 {
+    type Parsed<'a> = Vec<(Range, Range)>;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        parse_input(input)
+    }
+
+    fn part_one(pairs: &Self::Parsed<'_>) -> usize {
+        count_fully_contained_pairs(pairs)
+    }
 
-    let number_of_overlaps = count_overlaps(parse_input(INPUT));
-    println!("Number of overlaps: {number_of_overlaps}")
+    fn part_two(pairs: &Self::Parsed<'_>) -> usize {
+        count_overlaps(pairs)
+    }
 }
 
 #[cfg(test)]
@@ -113,24 +141,38 @@ mod test {
     fn test_parse_input() {
         assert_eq!(
             parse_input(SAMPLE),
-            vec![
+            Ok(vec![
                 (Range::new(2..4), Range::new(6..8)),
                 (Range::new(2..3), Range::new(4..5)),
                 (Range::new(5..7), Range::new(7..9)),
                 (Range::new(2..8), Range::new(3..7)),
                 (Range::new(6..6), Range::new(4..6)),
                 (Range::new(2..6), Range::new(4..8)),
-            ]
+            ])
         )
     }
 
     #[test]
     fn test_count_pairs() {
-        assert_eq!(count_fully_contained_pairs(parse_input(SAMPLE)), 2)
+        assert_eq!(
+            count_fully_contained_pairs(&parse_input(SAMPLE).unwrap()),
+            2
+        )
     }
 
     #[test]
     fn test_count_overlaps() {
-        assert_eq!(count_overlaps(parse_input(SAMPLE)), 4)
+        assert_eq!(count_overlaps(&parse_input(SAMPLE).unwrap()), 4)
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_input("2-4,6-8\n2-3;4-5").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.message, "expected two assignments separated by `,`");
+
+        let error = parse_input("2-4,6-x").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 7));
+        assert_eq!(error.message, "invalid number `x`");
     }
 }
@@ -1,40 +1,75 @@
-const INPUT: &str = include_str!("day_05.txt");
+use super::super::parse::{numbered_lines, parse_number, ParseError};
+use super::Solution;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
-struct Crate {
+pub struct Crate {
     id: char,
 }
 
-type Stacks = Vec<Vec<Crate>>;
-fn parse_stacks(input: &str) -> Stacks {
-    let lines = input.lines();
-    let as_crates: Stacks = lines
-        .take_while(|line| !line.trim().starts_with(char::is_numeric))
-        .map(|l| {
-            l.chars()
-                .collect::<Vec<char>>()
-                .chunks(4)
-                .map(|chars| Crate { id: chars[1] })
-                .collect()
+pub type Stacks = Vec<Vec<Crate>>;
+fn parse_crate_row(line_number: usize, line: &str) -> Result<Vec<Crate>, ParseError> {
+    line.chars()
+        .collect::<Vec<char>>()
+        .chunks(4)
+        .enumerate()
+        .map(|(idx, chars)| match chars {
+            ['[', id, ']', ..] => Ok(Crate { id: *id }),
+            [' ', ' ', ' ', ..] => Ok(Crate { id: ' ' }),
+            _ => Err(ParseError {
+                column: idx * 4 + 1,
+                len: chars.len().min(3),
+                ..ParseError::new(
+                    "expected a crate like `[A]` or an empty slot",
+                    line_number,
+                    line,
+                )
+            }),
         })
-        .collect();
-
-    let num_stacks = &as_crates.first().unwrap().len();
-    as_crates.iter().rev().fold(
-        (0..*num_stacks).map(|_| vec![]).collect(),
-        |mut result, crates| {
-            for (i, elem) in crates.iter().enumerate() {
-                if elem.id != ' ' {
-                    result[i].push(*elem)
-                }
-            }
-            result
-        },
-    )
+        .collect()
+}
+
+fn parse_stacks(input: &str) -> Result<Stacks, ParseError> {
+    let mut rows = vec![];
+    let mut last_line = (1, "");
+    for (line_number, line) in numbered_lines(input) {
+        if line.trim().starts_with(char::is_numeric) {
+            let num_stacks = line.split_whitespace().count();
+            return rows.iter().rev().try_fold(
+                (0..num_stacks).map(|_| vec![]).collect(),
+                |mut result: Stacks, (line_number, line, crates): &(usize, &str, Vec<Crate>)| {
+                    if crates.len() > num_stacks {
+                        return Err(ParseError::new(
+                            format!(
+                                "row has {} crates but there are only {num_stacks} stacks",
+                                crates.len()
+                            ),
+                            *line_number,
+                            line,
+                        ));
+                    }
+                    for (i, elem) in crates.iter().enumerate() {
+                        if elem.id != ' ' {
+                            result[i].push(*elem)
+                        }
+                    }
+                    Ok(result)
+                },
+            );
+        }
+        rows.push((line_number, line, parse_crate_row(line_number, line)?));
+        last_line = (line_number, line);
+    }
+
+    let (line_number, line) = last_line;
+    Err(ParseError::new(
+        "expected a line of stack numbers below the crates",
+        line_number,
+        line,
+    ))
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum Instruction {
+pub enum Instruction {
     Move { count: u32, from: usize, to: usize },
     MoveBatch { count: u32, from: usize, to: usize },
 }
@@ -45,35 +80,97 @@ enum InstructionParseMode {
     CrateMover9001,
 }
 
-type Instructions = Vec<Instruction>;
-fn parse_instructions(input: &str, mode: &InstructionParseMode) -> Instructions {
-    let instructions_string = input
-        .split('\n')
-        .skip_while(|ch| !ch.is_empty())
-        .collect::<Vec<&str>>()
-        .join("\n");
-    instructions_string
-        .trim()
-        .lines()
-        .map(|line| {
-            let mut parts = line.split(' ');
-            parts.next();
-            let count = parts.next().unwrap().parse().unwrap();
-            parts.next();
-            let from = parts.next().unwrap().parse().unwrap();
-            parts.next();
-            let to = parts.next().unwrap().parse().unwrap();
-            match mode {
+pub type Instructions = Vec<Instruction>;
+/// Parses the moves below the stacks, checking that each one takes crates from a stack holding
+/// enough of them and that every stack still has a crate on top in the end
+fn parse_instructions(
+    input: &str,
+    mode: &InstructionParseMode,
+    stacks: &Stacks,
+) -> Result<Instructions, ParseError> {
+    let num_stacks = stacks.len();
+    let mut heights: Vec<usize> = stacks.iter().map(Vec::len).collect();
+    // Where each stack was last emptied, to point at if it stays empty
+    let mut emptied = vec![None; num_stacks];
+    let instructions = numbered_lines(input)
+        .skip_while(|(_, line)| !line.is_empty())
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            let parts: Vec<&str> = line.trim().split(' ').collect();
+            let ["move", count_text, "from", from_text, "to", to_text] = parts.as_slice() else {
+                return Err(ParseError::new(
+                    "expected an instruction like `move 1 from 2 to 3`",
+                    line_number,
+                    line,
+                ));
+            };
+            let count: u32 = parse_number(line_number, line, count_text)?;
+            let from: usize = parse_number(line_number, line, from_text)?;
+            let to: usize = parse_number(line_number, line, to_text)?;
+            for (stack, text) in [(from, from_text), (to, to_text)] {
+                if stack == 0 || stack > num_stacks {
+                    return Err(ParseError::at(
+                        format!("there is no stack {stack}, stacks are numbered 1 to {num_stacks}"),
+                        line_number,
+                        line,
+                        text,
+                    ));
+                }
+            }
+            let moved = count as usize;
+            if moved > heights[from - 1] {
+                return Err(ParseError::at(
+                    format!(
+                        "can't move {count} crates, stack {from} only holds {}",
+                        heights[from - 1]
+                    ),
+                    line_number,
+                    line,
+                    count_text,
+                ));
+            }
+            heights[from - 1] -= moved;
+            if heights[from - 1] == 0 {
+                emptied[from - 1] = Some((line_number, line, *from_text));
+            }
+            heights[to - 1] += moved;
+            if moved > 0 {
+                emptied[to - 1] = None;
+            }
+            Ok(match mode {
                 InstructionParseMode::CrateMover9000 => Instruction::Move { count, from, to },
                 InstructionParseMode::CrateMover9001 => Instruction::MoveBatch { count, from, to },
-            }
+            })
         })
-        .collect()
+        .collect::<Result<Instructions, ParseError>>()?;
+
+    if let Some(stack) = heights.iter().position(|&height| height == 0) {
+        let message = format!(
+            "stack {} ends up empty, so it has no crate on top",
+            stack + 1
+        );
+        return Err(match emptied[stack] {
+            Some((line_number, line, from_text)) => {
+                ParseError::at(message, line_number, line, from_text)
+            }
+            None => {
+                // Empty from the start, point at its number below the crates
+                let (line_number, line) = numbered_lines(input)
+                    .find(|(_, line)| line.trim().starts_with(char::is_numeric))
+                    .unwrap_or((1, ""));
+                match line.split_whitespace().nth(stack) {
+                    Some(number) => ParseError::at(message, line_number, line, number),
+                    None => ParseError::new(message, line_number, line),
+                }
+            }
+        });
+    }
+    Ok(instructions)
 }
 
-fn apply_instructions(stacks: &mut Stacks, instructions: Instructions) {
+fn apply_instructions(stacks: &mut Stacks, instructions: &[Instruction]) {
     for instruction in instructions {
-        match instruction {
+        match *instruction {
             Instruction::Move { count, from, to } => {
                 for _ in 0..count {
                     let source_crate = stacks[from - 1].pop().unwrap();
@@ -92,21 +189,38 @@ fn apply_instructions(stacks: &mut Stacks, instructions: Instructions) {
     }
 }
 
-fn run_simulation(input: &str, mode: InstructionParseMode) {
-    let mut stacks = parse_stacks(input);
-    let instructions = parse_instructions(input, &mode);
-
+fn top_crates(stacks: &Stacks, instructions: &[Instruction]) -> String {
+    let mut stacks = stacks.clone();
     apply_instructions(&mut stacks, instructions);
-    let result: String = stacks
+    stacks
         .iter()
         .map(|stack| stack.last().unwrap().id)
-        .collect();
-    println!("Result with {mode:?}: {result}")
+        .collect()
 }
 
-pub fn print_solution() {
-    run_simulation(INPUT, InstructionParseMode::CrateMover9000);
-    run_simulation(INPUT, InstructionParseMode::CrateMover9001);
+pub struct Day05;
+
+impl Solution for Day05 {
+    type Parsed<'a> = (Stacks, Instructions, Instructions);
+    type PartOne = String;
+    type PartTwo = String;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        let stacks = parse_stacks(input)?;
+        let instructions =
+            parse_instructions(input, &InstructionParseMode::CrateMover9000, &stacks)?;
+        let batch_instructions =
+            parse_instructions(input, &InstructionParseMode::CrateMover9001, &stacks)?;
+        Ok((stacks, instructions, batch_instructions))
+    }
+
+    fn part_one((stacks, instructions, _): &Self::Parsed<'_>) -> String {
+        top_crates(stacks, instructions)
+    }
+
+    fn part_two((stacks, _, batch_instructions): &Self::Parsed<'_>) -> String {
+        top_crates(stacks, batch_instructions)
+    }
 }
 
 #[cfg(test)]
@@ -130,11 +244,11 @@ move 1 from 1 to 2
     fn test_parse_input() {
         assert_eq!(
             parse_stacks(SAMPLE.trim_matches('\n')),
-            vec![
+            Ok(vec![
                 vec![Crate { id: 'Z' }, Crate { id: 'N' }],
                 vec![Crate { id: 'M' }, Crate { id: 'C' }, Crate { id: 'D' }],
                 vec![Crate { id: 'P' }],
-            ]
+            ])
         );
     }
 
@@ -143,9 +257,10 @@ move 1 from 1 to 2
         assert_eq!(
             parse_instructions(
                 SAMPLE.trim_matches('\n'),
-                &InstructionParseMode::CrateMover9000
+                &InstructionParseMode::CrateMover9000,
+                &parse_stacks(SAMPLE.trim_matches('\n')).unwrap()
             ),
-            vec![
+            Ok(vec![
                 Instruction::Move {
                     count: 1,
                     from: 2,
@@ -166,18 +281,20 @@ move 1 from 1 to 2
                     from: 1,
                     to: 2
                 },
-            ]
+            ])
         )
     }
 
     #[test]
     fn test_apply() {
-        let mut stacks = parse_stacks(SAMPLE.trim_matches('\n'));
+        let mut stacks = parse_stacks(SAMPLE.trim_matches('\n')).unwrap();
         let instructions = parse_instructions(
             SAMPLE.trim_matches('\n'),
             &InstructionParseMode::CrateMover9000,
-        );
-        apply_instructions(&mut stacks, instructions);
+            &stacks,
+        )
+        .unwrap();
+        apply_instructions(&mut stacks, &instructions);
         assert_eq!(
             stacks,
             vec![
@@ -195,12 +312,14 @@ move 1 from 1 to 2
 
     #[test]
     fn test_apply_batch() {
-        let mut stacks = parse_stacks(SAMPLE.trim_matches('\n'));
+        let mut stacks = parse_stacks(SAMPLE.trim_matches('\n')).unwrap();
         let instructions = parse_instructions(
             SAMPLE.trim_matches('\n'),
             &InstructionParseMode::CrateMover9001,
-        );
-        apply_instructions(&mut stacks, instructions);
+            &stacks,
+        )
+        .unwrap();
+        apply_instructions(&mut stacks, &instructions);
         assert_eq!(
             stacks,
             vec![
@@ -215,4 +334,47 @@ move 1 from 1 to 2
             ]
         );
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_stacks("[A] {B}\n 1   2 ").unwrap_err();
+        assert_eq!((error.line, error.column, error.len), (1, 5, 3));
+
+        let error = parse_stacks("[A]").unwrap_err();
+        assert_eq!(
+            error.message,
+            "expected a line of stack numbers below the crates"
+        );
+
+        let error = parse_instructions(
+            "[A]\n 1 \n\nmove 1 from 1 to 1\nmove x from 1 to 1",
+            &InstructionParseMode::CrateMover9000,
+            &vec![vec![Crate { id: 'A' }]],
+        )
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (5, 6));
+
+        let error = Day05::parse("[A] [B]\n 1   2 \n\nmove 1 from 9 to 1").unwrap_err();
+        assert_eq!(
+            error.message,
+            "there is no stack 9, stacks are numbered 1 to 2"
+        );
+        assert_eq!((error.line, error.column, error.len), (4, 13, 1));
+        let error = Day05::parse("[A]\n 1 \n\nmove 1 from 1 to 0").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 18));
+
+        let error =
+            Day05::parse("[A] [B]\n 1   2 \n\nmove 1 from 1 to 2\nmove 3 from 2 to 1").unwrap_err();
+        assert_eq!(error.message, "can't move 3 crates, stack 2 only holds 2");
+        assert_eq!((error.line, error.column), (5, 6));
+
+        let error = Day05::parse("[A] [B]\n 1   2 \n\nmove 1 from 1 to 2").unwrap_err();
+        assert_eq!(
+            error.message,
+            "stack 1 ends up empty, so it has no crate on top"
+        );
+        assert_eq!((error.line, error.column), (4, 13));
+        let error = Day05::parse("[A]    \n 1   2 \n\nmove 1 from 1 to 1").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 6));
+    }
 }
@@ -1,7 +1,7 @@
+use super::super::parse::{numbered_lines, ParseError};
+use super::Solution;
 use std::{collections::HashSet, hash::Hash, str::Chars};
 
-const INPUT: &str = include_str!("day_06.txt");
-
 fn next_chunk(chars: &mut Chars, count: usize) -> Vec<char> {
     let mut result = vec![];
     for _ in 0..count {
@@ -33,15 +33,63 @@ fn index_for_unique_chars(in_str: &str, count: usize) -> usize {
             .count()
 }
 
-pub fn print_solution() {
-    println!(
-        "Start of packet sequence: {}",
-        index_for_unique_chars(INPUT, 4)
-    );
-    println!(
-        "Start of message sequence: {}",
-        index_for_unique_chars(INPUT, 14)
-    );
+fn parse_input(input: &str) -> Result<&str, ParseError> {
+    let datastream = input.trim();
+    if let Some((line_number, line)) = numbered_lines(input)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .nth(1)
+    {
+        return Err(ParseError::new(
+            "expected a single line datastream",
+            line_number,
+            line,
+        ));
+    }
+    let (line_number, line) = numbered_lines(input)
+        .find(|(_, line)| !line.trim().is_empty())
+        .unwrap_or((1, ""));
+    if let Some((idx, ch)) = datastream
+        .char_indices()
+        .find(|(_, ch)| !ch.is_ascii_lowercase())
+    {
+        return Err(ParseError::at(
+            format!("unexpected character `{ch}` in datastream"),
+            line_number,
+            line,
+            &datastream[idx..idx + ch.len_utf8()],
+        ));
+    }
+    if datastream.len() < 14 {
+        return Err(ParseError::new(
+            format!(
+                "datastream has {} characters, at least 14 are needed",
+                datastream.len()
+            ),
+            line_number,
+            line,
+        ));
+    }
+    Ok(datastream)
+}
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    type Parsed<'a> = &'a str;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        parse_input(input)
+    }
+
+    fn part_one(datastream: &Self::Parsed<'_>) -> usize {
+        index_for_unique_chars(datastream, 4)
+    }
+
+    fn part_two(datastream: &Self::Parsed<'_>) -> usize {
+        index_for_unique_chars(datastream, 14)
+    }
 }
 
 #[cfg(test)]
@@ -81,4 +129,25 @@ mod test {
         assert!(all_unique(&vec![3, 4, 5]));
         assert!(!all_unique(&vec![3, 3, 4, 5]));
     }
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(
+            parse_input("mjqjpqmgbljsphdztnvjfqwrcgsmlb\n"),
+            Ok("mjqjpqmgbljsphdztnvjfqwrcgsmlb")
+        );
+        assert_eq!(parse_input("mjqjp").unwrap_err().line, 1);
+        assert_eq!(
+            parse_input("mjqjpqmgblj5phdztnvjfqwrcgsmlb")
+                .unwrap_err()
+                .column,
+            12
+        );
+        assert_eq!(
+            parse_input("mjqjpqmgbljsphdztnvjfqwrcgsmlb\nabc")
+                .unwrap_err()
+                .line,
+            2
+        );
+    }
 }
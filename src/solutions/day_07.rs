@@ -1,84 +1,222 @@
+use super::super::parse::{numbered_lines, parse_number, ParseError};
 use super::super::tree::*;
+use super::Solution;
+use std::collections::HashSet;
 
-const INPUT: &str = include_str!("day_07.txt");
-
-fn parse_input(input: &str) -> FSNode {
-    let root = FSNode::new(String::from("/"));
-    let mut current = root.zipper();
-    let mut lines = input.lines();
-
-    lines.next(); // Skip the first line since it is `cd /`
-    for command in lines {
-        match command {
-            s if s.starts_with("$ cd ..") => current = current.parent(),
-            s if s.starts_with("$ cd") => {
-                let child_path = s.strip_prefix("$ cd ").unwrap();
-                current = current.child(&String::from(child_path)).unwrap()
-            }
-            s if s.starts_with("$ ls") => (),
-            s if s.starts_with("dir") => {
-                let new_dir_path = s.strip_prefix("dir ").unwrap();
-                current = current.add_child(FSNode::new(String::from(new_dir_path)));
-            }
-            s if s.starts_with(char::is_numeric) => {
-                let mut parts = s.split(' ');
-                let size = parts.next().unwrap().parse().unwrap();
-                let name = String::from(parts.next().unwrap());
-                current = current.add_child(FSNode::File(name, size))
-            }
-            _ => unreachable!("Command {} has unknown format", command),
-        };
-    }
-    current.finish()
+/// Size of the device in the puzzle
+pub const DISK_CAPACITY: usize = 70_000_000;
+/// Free space the update needs
+pub const REQUIRED_SPACE: usize = 30_000_000;
+
+/// What to do on `cd` into a directory that no `ls` has shown so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnseenDirectories {
+    #[default]
+    Error,
+    Create,
+}
+
+/// The filesystem reconstructed from a terminal session
+#[derive(Debug, PartialEq)]
+pub struct Session {
+    pub root: FSNode,
+    /// Directories that were never listed, so their sizes may be incomplete
+    pub unlisted: Vec<FSPath>,
+}
+
+fn change_directory(
+    current: &mut FSNodeZipper,
+    path: &FSPath,
+    unseen: UnseenDirectories,
+) -> Result<(), String> {
+    if path.is_absolute() {
+        current.root();
+    }
+    for name in path.components() {
+        if name != ".."
+            && unseen == UnseenDirectories::Create
+            && current.node().child(name).is_none()
+        {
+            current.add_child(FSNode::new(name.clone()));
+        }
+        current
+            .cd(&FSPath::from(name.as_str()))
+            .map_err(|err| match err {
+                NavigationError::AboveRoot => String::from("cannot leave the root directory"),
+                NavigationError::NoSuchChild(name) => {
+                    format!("directory `{name}` was not listed before")
+                }
+                NavigationError::NotADirectory(name) => format!("`{name}` is a file"),
+            })?;
+    }
+    Ok(())
+}
+
+fn parse_entry(line_number: usize, line: &str) -> Result<FSNode, ParseError> {
+    let Some((kind, name)) = line.split_once(' ') else {
+        return Err(ParseError::new(
+            "expected an entry like `dir a` or `1234 b.txt`",
+            line_number,
+            line,
+        ));
+    };
+    if kind == "dir" {
+        return Ok(FSNode::new(String::from(name)));
+    }
+    let size = parse_number(line_number, line, kind)?;
+    Ok(FSNode::file(String::from(name), size))
+}
+
+/// Adds a listed entry, which may already be known from an earlier `ls` of the same directory
+fn add_entry(current: &mut FSNodeZipper, entry: FSNode) -> Result<(), String> {
+    let name = entry.name();
+    match (
+        current.node().child(&name).map(|c| c.entry()),
+        entry.entry(),
+    ) {
+        (None, _) => {
+            current.add_child(entry);
+            Ok(())
+        }
+        // Keep what is already known about the directory's contents
+        (Some(FSEntry::Directory(_)), FSEntry::Directory(_)) => Ok(()),
+        (Some(FSEntry::File(_, before)), FSEntry::File(_, after)) if before == after => Ok(()),
+        (Some(FSEntry::File(_, before)), FSEntry::File(..)) => {
+            Err(format!("`{name}` was listed with size {before} before"))
+        }
+        (Some(FSEntry::File(..)), FSEntry::Directory(_)) => {
+            Err(format!("`{name}` was listed as a file before"))
+        }
+        (Some(FSEntry::Directory(_)), FSEntry::File(..)) => {
+            Err(format!("`{name}` was listed as a directory before"))
+        }
+        (Some(_), _) => Err(format!("`{name}` was listed differently before")),
+    }
+}
+
+/// Replays a terminal session of `cd` and `ls` commands to rebuild the filesystem
+pub fn interpret(input: &str, unseen: UnseenDirectories) -> Result<Session, ParseError> {
+    let mut current = FSNode::new(String::from("/")).zipper();
+    let mut listed = HashSet::new();
+    let mut listing = false;
+
+    for (line_number, line) in numbered_lines(input) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("$ cd ") {
+            listing = false;
+            change_directory(&mut current, &FSPath::from(path), unseen)
+                .map_err(|message| ParseError::at(message, line_number, line, path))?;
+        } else if line == "$ ls" {
+            listing = true;
+            listed.insert(current.current_path());
+        } else if line.starts_with('$') {
+            return Err(ParseError::new(
+                format!("unknown command `{line}`"),
+                line_number,
+                line,
+            ));
+        } else if !listing {
+            return Err(ParseError::new(
+                "entry listed without a preceding `$ ls`",
+                line_number,
+                line,
+            ));
+        } else {
+            let entry = parse_entry(line_number, line)?;
+            let name = entry.name();
+            add_entry(&mut current, entry).map_err(|message| {
+                ParseError::at(message, line_number, line, &line[line.len() - name.len()..])
+            })?;
+        }
+    }
+
+    let root = current.finish();
+    let mut unlisted: Vec<FSPath> = root
+        .paths()
+        .into_iter()
+        .filter(|(path, node)| node.is_directory() && !listed.contains(path))
+        .map(|(path, _)| path)
+        .collect();
+    unlisted.sort();
+    Ok(Session { root, unlisted })
 }
 
-fn nodes_below_size(start: &FSNode, size: usize) -> Vec<&FSNode> {
-    all_nodes(start)
-        .iter()
-        .filter(|&&node| node.size_below() < size && node.is_directory())
-        .copied()
+pub fn parse_input(input: &str) -> Result<FSNode, ParseError> {
+    interpret(input, UnseenDirectories::Error).map(|session| session.root)
+}
+
+pub fn nodes_below_size(start: &FSNode, size: usize) -> Vec<&FSNode> {
+    start
+        .directory_sizes()
+        .into_iter()
+        .filter(|&(_, total)| total < size)
+        .map(|(node, _)| node)
         .collect()
 }
 
-fn all_nodes(start: &FSNode) -> Vec<&FSNode> {
-    let mut result = vec![];
-    start.visit_nodes(|node| result.push(node));
-    result
+pub fn all_nodes(start: &FSNode) -> Vec<&FSNode> {
+    start.iter_bfs().map(|(_, node)| node).collect()
 }
 
-fn smallest_directory_that_fits(start: &FSNode, minimum_space_required: usize) -> usize {
-    all_nodes(start)
-        .iter()
-        .filter_map(|node| {
+pub fn smallest_directory_that_fits(start: &FSNode, minimum_space_required: usize) -> usize {
+    start
+        .fold(|node, children: Vec<Option<usize>>| {
             let size = node.size_below();
-            if size >= minimum_space_required && node.is_directory() {
-                Some(size)
-            } else {
-                None
-            }
+            let fits = (node.is_directory() && size >= minimum_space_required).then_some(size);
+            children.into_iter().flatten().chain(fits).min()
         })
-        .min()
         .unwrap()
 }
 
-pub fn print_solution() {
-    let filesystem = parse_input(INPUT);
-    let deletion_candidates = nodes_below_size(&filesystem, 100_000);
-    let freed_space: usize = deletion_candidates.iter().map(|&n| n.size_below()).sum();
-    println!("Amount of space that can be freed: {freed_space}");
-    let total_space = 70_000_000;
-    let space_required = 30_000_000;
-    let free_space = total_space - filesystem.size_below();
-    let space_to_free = space_required - free_space;
-    let smallest_dir = smallest_directory_that_fits(&filesystem, space_to_free);
-    println!("Smallest directory that frees enough space: {smallest_dir}");
+pub struct Day07;
+
+impl Solution for Day07 {
+    type Parsed<'a> = FSNode;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    /// Also rejects filesystems that don't fit on the disk, pointing at the largest file
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        let filesystem = parse_input(input)?;
+        let used = filesystem.size_below();
+        if used > DISK_CAPACITY {
+            let largest = numbered_lines(input)
+                .filter_map(|(line_number, line)| {
+                    let (size, _) = line.trim().split_once(' ')?;
+                    Some((size.parse::<usize>().ok()?, line_number, line, size))
+                })
+                .max_by_key(|&(size, ..)| size);
+            let message =
+                format!("the files take up {used} bytes, but the disk only holds {DISK_CAPACITY}");
+            return Err(match largest {
+                Some((_, line_number, line, size)) => {
+                    ParseError::at(message, line_number, line, size)
+                }
+                None => ParseError::new(message, 1, input.lines().next().unwrap_or("")),
+            });
+        }
+        Ok(filesystem)
+    }
+
+    fn part_one(filesystem: &Self::Parsed<'_>) -> usize {
+        let deletion_candidates = nodes_below_size(filesystem, 100_000);
+        deletion_candidates.iter().map(|&n| n.size_below()).sum()
+    }
+
+    fn part_two(filesystem: &Self::Parsed<'_>) -> usize {
+        // `parse` made sure everything fits on the disk
+        let free_space = DISK_CAPACITY - filesystem.size_below();
+        let space_to_free = REQUIRED_SPACE.saturating_sub(free_space);
+        smallest_directory_that_fits(filesystem, space_to_free)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use pretty_assertions::assert_eq;
-    use std::collections::HashMap;
 
     const SAMPLE: &str = "$ cd /
 $ ls
@@ -124,39 +262,37 @@ $ ls
     fn test_parse_input() {
         assert_eq!(
             parse_input(SHORT_SAMPLE),
-            FSNode::Node(
-                s("/"),
-                HashMap::from([
-                    (s("b.txt"), FSNode::File(s("b.txt"), 14848514)),
-                    (s("c.dat"), FSNode::File(s("c.dat"), 8504156)),
-                    (
-                        s("d"),
-                        FSNode::Node(
-                            s("d"),
-                            HashMap::from([
-                                (s("j"), FSNode::File(s("j"), 4060174)),
-                                (s("d.log"), FSNode::File(s("d.log"), 8033020)),
-                                (s("d.ext"), FSNode::File(s("d.ext"), 5626152)),
-                                (s("k"), FSNode::File(s("k"), 7214296))
-                            ])
-                        )
-                    ),
-                ])
+            FSNode::from_lines(
+                "dir /
+8504156 /c.dat
+14848514 /b.txt
+dir /d
+5626152 /d/d.ext
+8033020 /d/d.log
+4060174 /d/j
+7214296 /d/k"
             )
         )
     }
 
+    #[test]
+    fn test_transcript_round_trip() {
+        let input = parse_input(SAMPLE).unwrap();
+        assert_eq!(parse_input(&input.to_transcript()), Ok(input));
+    }
+
     #[test]
     fn test_size_below() {
-        let nodes = parse_input(SAMPLE);
+        let nodes = parse_input(SAMPLE).unwrap();
         assert_eq!(nodes.size_below(), 48381165)
     }
 
     #[test]
     fn test_visit() {
         let mut node_names = vec![];
-        parse_input(SHORT_SAMPLE).visit_nodes(|node| node_names.push(node.name()));
-        node_names.sort();
+        parse_input(SHORT_SAMPLE)
+            .unwrap()
+            .visit_nodes(|node| node_names.push(node.name()));
         assert_eq!(
             node_names,
             vec![
@@ -164,9 +300,9 @@ $ ls
                 s("b.txt"),
                 s("c.dat"),
                 s("d"),
-                s("d.ext"),
-                s("d.log"),
                 s("j"),
+                s("d.log"),
+                s("d.ext"),
                 s("k")
             ]
         )
@@ -174,20 +310,114 @@ $ ls
 
     #[test]
     fn test_nodes_below_size() {
-        let input = parse_input(SAMPLE);
+        let input = parse_input(SAMPLE).unwrap();
         let below_100k = nodes_below_size(&input, 100000);
-        assert_eq!(
-            vec![s("a"), s("e")],
-            below_100k
-                .iter()
-                .map(|&n| n.name())
-                .collect::<Vec<String>>(),
-        )
+        let paths: Vec<String> = below_100k
+            .iter()
+            .map(|&n| input.path_of(n).unwrap().to_string())
+            .collect();
+        assert_eq!(paths, vec![s("/a"), s("/a/e")])
     }
 
     #[test]
     fn test_smallest_dir() {
-        let input = parse_input(SAMPLE);
+        let input = parse_input(SAMPLE).unwrap();
         assert_eq!(smallest_directory_that_fits(&input, 8381165), 24933642);
     }
+
+    #[test]
+    fn test_queries() {
+        let input = parse_input(SAMPLE).unwrap();
+        let query = |query: &str| input.query(&query.parse().unwrap());
+        assert_eq!(
+            query("sum type:dir size<100000"),
+            QueryResult::Value(Some(Day07::part_one(&input)))
+        );
+        assert_eq!(
+            query("min type:dir size>=8381165"),
+            QueryResult::Value(Some(24933642))
+        );
+    }
+
+    #[test]
+    fn test_cleanup_plan() {
+        // The planner agrees with part two
+        let plan = parse_input(SAMPLE)
+            .unwrap()
+            .plan_cleanup(&CleanupOptions {
+                capacity: DISK_CAPACITY,
+                required: REQUIRED_SPACE,
+                objective: Objective::FewestDeletions,
+                protected: vec![],
+            })
+            .unwrap();
+        assert_eq!(plan.deletions, vec![(FSPath::from("/d"), 24933642)]);
+        assert_eq!(Day07::part_two(&parse_input(SAMPLE).unwrap()), 24933642);
+    }
+
+    #[test]
+    fn test_overfull_disk() {
+        let error = Day07::parse("$ cd /\n$ ls\n80000000 a\n1 b").unwrap_err();
+        assert_eq!(
+            error.message,
+            "the files take up 80000001 bytes, but the disk only holds 70000000"
+        );
+        assert_eq!((error.line, error.column, error.len), (3, 1, 8));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_input("$ cd /\n$ ls\ndir a\n$ cd b").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 6));
+        assert_eq!(error.message, "directory `b` was not listed before");
+
+        let error = parse_input("$ cd /\n$ ls\n12x3 a").unwrap_err();
+        assert_eq!(error.message, "invalid number `12x3`");
+
+        let error = parse_input("$ cd /\n12 a").unwrap_err();
+        assert_eq!(error.message, "entry listed without a preceding `$ ls`");
+
+        let error = parse_input("$ cd /\n$ rm -rf a").unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let error = parse_input("$ cd /\n$ cd ..").unwrap_err();
+        assert_eq!(error.message, "cannot leave the root directory");
+    }
+
+    #[test]
+    fn test_cd_root_mid_session() {
+        let input = parse_input(
+            "$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\n10 x\n$ cd /\n$ ls\n5 y\n$ cd a\n$ cd ../a",
+        )
+        .unwrap();
+        assert_eq!(input.size_below(), 15);
+        assert_eq!(input.get(&FSPath::from("/y")).unwrap().size_below(), 5);
+    }
+
+    #[test]
+    fn test_repeated_ls() {
+        let session = "$ cd /\n$ ls\ndir a\n1 b\n$ cd a\n$ ls\n2 c\n$ cd /\n$ ls\ndir a\n1 b";
+        assert_eq!(parse_input(session).unwrap().size_below(), 3);
+
+        let error = parse_input("$ cd /\n$ ls\n1 b\n$ ls\n5 b").unwrap_err();
+        assert_eq!(error.message, "`b` was listed with size 1 before");
+        assert_eq!((error.line, error.column), (5, 3));
+
+        let error = parse_input("$ cd /\n$ ls\n1 b\n$ ls\ndir b").unwrap_err();
+        assert_eq!(error.message, "`b` was listed as a file before");
+    }
+
+    #[test]
+    fn test_unseen_directories() {
+        let session = "$ cd /a/b\n$ ls\n7 c\n$ cd /\n$ ls\ndir a\ndir d";
+        let error = interpret(session, UnseenDirectories::Error).unwrap_err();
+        assert_eq!(error.message, "directory `a` was not listed before");
+
+        let session = interpret(session, UnseenDirectories::Create).unwrap();
+        assert_eq!(session.root.size_below(), 7);
+        assert_eq!(
+            session.unlisted,
+            vec![FSPath::from("/a"), FSPath::from("/d")]
+        );
+    }
 }
@@ -1,15 +1,50 @@
-const INPUT: &str = include_str!("day_08.txt");
+use super::super::parse::{numbered_lines, ParseError};
+use super::Solution;
 
-fn parse_input(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|line| {
-            line.trim()
-                .chars()
-                .map(|ch| ch.to_digit(10).unwrap())
-                .collect()
+fn parse_input(input: &str) -> Result<Vec<Vec<u32>>, ParseError> {
+    let trees = numbered_lines(input)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            let row = line.trim();
+            row.char_indices()
+                .map(|(idx, ch)| {
+                    ch.to_digit(10).ok_or_else(|| {
+                        ParseError::at(
+                            format!("expected a tree height, got `{ch}`"),
+                            line_number,
+                            line,
+                            &row[idx..idx + ch.len_utf8()],
+                        )
+                    })
+                })
+                .collect::<Result<Vec<u32>, ParseError>>()
+                .map(|heights| (line_number, line, heights))
         })
-        .collect()
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    // `transpose` relies on there being a first row, and every row having the same width
+    let Some((_, _, first)) = trees.first() else {
+        return Err(ParseError::new(
+            "expected at least one row of trees",
+            1,
+            input.lines().next().unwrap_or(""),
+        ));
+    };
+    if let Some((line_number, line, row)) =
+        trees.iter().find(|(_, _, row)| row.len() != first.len())
+    {
+        return Err(ParseError::new(
+            format!(
+                "row has {} trees, expected {} like the first row",
+                row.len(),
+                first.len()
+            ),
+            *line_number,
+            line,
+        ));
+    }
+
+    Ok(trees.into_iter().map(|(_, _, row)| row).collect())
 }
 
 fn map_tallest() -> impl FnMut(&u32) -> bool {
@@ -46,7 +81,7 @@ fn map_visible_trees() -> impl FnMut(&u32) -> u32 {
     }
 }
 
-fn transpose<T: Copy>(row_wise: &Vec<Vec<T>>) -> Vec<Vec<T>> {
+fn transpose<T: Copy>(row_wise: &[Vec<T>]) -> Vec<Vec<T>> {
     let row_length = row_wise[0].len();
     let col_length = row_wise.len();
     let mut result: Vec<Vec<T>> = (0..row_length)
@@ -61,7 +96,7 @@ fn transpose<T: Copy>(row_wise: &Vec<Vec<T>>) -> Vec<Vec<T>> {
 }
 
 fn compute_directionally<F, Mapper, Combine, T>(
-    trees: Vec<Vec<u32>>,
+    trees: &[Vec<u32>],
     mapper: Mapper,
     combine: Combine,
 ) -> Vec<Vec<T>>
@@ -85,12 +120,12 @@ where
         })
         .collect();
 
-    let from_top: Vec<Vec<T>> = transpose(&trees)
+    let from_top: Vec<Vec<T>> = transpose(trees)
         .iter()
         .map(|line| line.iter().map(mapper()).collect())
         .collect();
 
-    let from_bottom: Vec<Vec<T>> = transpose(&trees)
+    let from_bottom: Vec<Vec<T>> = transpose(trees)
         .iter()
         .map(|line| {
             let mut visibility: Vec<T> = line.iter().rev().map(mapper()).collect();
@@ -115,22 +150,22 @@ where
         .collect()
 }
 
-fn compute_visibility(trees: Vec<Vec<u32>>) -> Vec<Vec<bool>> {
+fn compute_visibility(trees: &[Vec<u32>]) -> Vec<Vec<bool>> {
     compute_directionally(trees, map_tallest, |w, x, y, z| w || x || y || z)
 }
 
-fn compute_scenic_score(trees: Vec<Vec<u32>>) -> Vec<Vec<u32>> {
+fn compute_scenic_score(trees: &[Vec<u32>]) -> Vec<Vec<u32>> {
     compute_directionally(trees, map_visible_trees, |w, x, y, z| w * x * y * z)
 }
 
-fn count_visible(trees: Vec<Vec<bool>>) -> usize {
+fn count_visible(trees: &[Vec<bool>]) -> usize {
     trees
         .iter()
         .map(|line| line.iter().map(|&tree| usize::from(tree)).sum::<usize>())
         .sum()
 }
 
-fn highest_score(scores: Vec<Vec<u32>>) -> u32 {
+fn highest_score(scores: &[Vec<u32>]) -> u32 {
     *scores
         .iter()
         .map(|line| line.iter().max().unwrap_or(&0))
@@ -138,14 +173,24 @@ fn highest_score(scores: Vec<Vec<u32>>) -> u32 {
         .unwrap_or(&0)
 }
 
-pub fn print_solution() {
-    let trees = parse_input(INPUT);
-    let visible_trees = compute_visibility(trees.clone());
-    let visible_tree_count = count_visible(visible_trees);
-    println!("Number of visible trees: {visible_tree_count}");
-    let scenic_scores = compute_scenic_score(trees);
-    let highest_score = highest_score(scenic_scores);
-    println!("Highest scenic score: {highest_score}");
+pub struct Day08;
+
+impl Solution for Day08 {
+    type Parsed<'a> = Vec<Vec<u32>>;
+    type PartOne = usize;
+    type PartTwo = u32;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        parse_input(input)
+    }
+
+    fn part_one(trees: &Self::Parsed<'_>) -> usize {
+        count_visible(&compute_visibility(trees))
+    }
+
+    fn part_two(trees: &Self::Parsed<'_>) -> u32 {
+        highest_score(&compute_scenic_score(trees))
+    }
 }
 
 #[cfg(test)]
@@ -163,20 +208,20 @@ mod test {
     fn test_parse_input() {
         assert_eq!(
             parse_input(SAMPLE),
-            vec![
+            Ok(vec![
                 vec![3, 0, 3, 7, 3],
                 vec![2, 5, 5, 1, 2],
                 vec![6, 5, 3, 3, 2],
                 vec![3, 3, 5, 4, 9],
                 vec![3, 5, 3, 9, 0],
-            ]
+            ])
         );
     }
 
     #[test]
     fn test_compute_visibility() {
         assert_eq!(
-            compute_visibility(parse_input(SAMPLE)),
+            compute_visibility(&parse_input(SAMPLE).unwrap()),
             vec![
                 vec![true, true, true, true, true],
                 vec![true, true, true, false, true],
@@ -189,19 +234,22 @@ mod test {
 
     #[test]
     fn test_compute_scenic_score() {
-        let scores = compute_scenic_score(parse_input(SAMPLE));
-        assert_eq!(highest_score(scores), 8)
+        let scores = compute_scenic_score(&parse_input(SAMPLE).unwrap());
+        assert_eq!(highest_score(&scores), 8)
     }
 
     #[test]
     fn test_count_visible() {
-        assert_eq!(count_visible(compute_visibility(parse_input(SAMPLE))), 21);
+        assert_eq!(
+            count_visible(&compute_visibility(&parse_input(SAMPLE).unwrap())),
+            21
+        );
     }
 
     #[test]
     fn test_transpose() {
         assert_eq!(
-            transpose(&vec![vec![1, 2, 3], vec![4, 5, 6],]),
+            transpose(&[vec![1, 2, 3], vec![4, 5, 6]]),
             vec![vec![1, 4], vec![2, 5], vec![3, 6]]
         );
     }
@@ -215,4 +263,19 @@ mod test {
         assert_eq!(trans(vec![3, 3, 5, 4, 9]), vec![0, 1, 2, 1, 4]);
         assert_eq!(trans(vec![3, 5, 3, 9, 0]), vec![0, 1, 1, 3, 1]);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_input("303\n2x5").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+
+        let error = parse_input("\n").unwrap_err();
+        assert_eq!(error.message, "expected at least one row of trees");
+
+        let error = parse_input("303\n25").unwrap_err();
+        assert_eq!(
+            error.message,
+            "row has 2 trees, expected 3 like the first row"
+        );
+    }
 }
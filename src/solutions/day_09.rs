@@ -1,41 +1,41 @@
+use super::super::parse::{numbered_lines, parse_number, ParseError};
+use super::Solution;
 use std::collections::hash_map::RandomState;
 use std::collections::HashSet;
-use std::str::Lines;
-
-const INPUT: &str = include_str!("day_09.txt");
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Position {
-    x: i32,
-    y: i32,
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
-enum Direction {
+pub enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
 
-#[derive(Debug, PartialEq)]
-struct Instruction {
-    count: u32,
-    direction: Direction,
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Instruction {
+    pub count: u32,
+    pub direction: Direction,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-struct RopePosition<const N: usize> {
-    positions: [Position; N],
+pub struct RopePosition<const N: usize> {
+    pub positions: [Position; N],
 }
 
-struct Rope<'a, const N: usize> {
+/// Simulates a rope with `N` knots, yielding the knot positions after every step
+pub struct Rope<I, const N: usize> {
     positions: [Position; N],
-    instructions: &'a mut ExpandedInstructions<'a>,
+    instructions: ExpandedInstructions<I>,
 }
 
-impl<'a, const N: usize> Rope<'a, N> {
-    fn new(instructions: &'a mut ExpandedInstructions<'a>) -> Rope<N> {
+impl<I, const N: usize> Rope<I, N> {
+    pub fn new(instructions: ExpandedInstructions<I>) -> Rope<I, N> {
         Rope {
             positions: [Position { x: 0, y: 0 }; N],
             instructions,
@@ -63,13 +63,11 @@ impl<'a, const N: usize> Rope<'a, N> {
     }
 }
 
-impl<'a, const N: usize> Iterator for Rope<'a, N> {
+impl<I: Iterator<Item = Instruction>, const N: usize> Iterator for Rope<I, N> {
     type Item = RopePosition<N>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let Some(direction) = self.instructions.next() else {
-            return None;
-        };
+        let direction = self.instructions.next()?;
 
         match direction {
             Direction::Up => self.positions[0].y += 1,
@@ -88,57 +86,14 @@ impl<'a, const N: usize> Iterator for Rope<'a, N> {
     }
 }
 
-struct Instructions<'a> {
-    lines: &'a mut Lines<'a>,
-}
-
-impl<'a> Instructions<'a> {
-    fn expand(&'a mut self) -> ExpandedInstructions {
-        ExpandedInstructions {
-            instructions: self,
-            current_count: None,
-            current_direction: None,
-        }
-    }
-}
-
-impl<'a> Iterator for Instructions<'a> {
-    type Item = Instruction;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let Some(line) = self.lines.next() else {
-            return None;
-        };
-
-        let mut parts = line.trim().split(' ');
-        let direction = parts.next();
-        let count = parts.next().map(|p| p.parse::<u32>().ok());
-
-        let Some(direction) = direction else { return None; };
-        let Some(Some(count)) = count else { return None; };
-
-        let dir_enum = match direction {
-            "R" => Direction::Right,
-            "U" => Direction::Up,
-            "D" => Direction::Down,
-            "L" => Direction::Left,
-            _ => unreachable!(),
-        };
-
-        Some(Instruction {
-            count,
-            direction: dir_enum,
-        })
-    }
-}
-
-struct ExpandedInstructions<'a> {
-    instructions: &'a mut Instructions<'a>,
+/// Splits instructions into single steps
+pub struct ExpandedInstructions<I> {
+    instructions: I,
     current_count: Option<u32>,
     current_direction: Option<Direction>,
 }
 
-impl<'a> Iterator for ExpandedInstructions<'a> {
+impl<I: Iterator<Item = Instruction>> Iterator for ExpandedInstructions<I> {
     type Item = Direction;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -149,7 +104,7 @@ impl<'a> Iterator for ExpandedInstructions<'a> {
             }
         }
 
-        let Some(next_instruction) = self.instructions.next() else { return None; };
+        let next_instruction = self.instructions.next()?;
 
         self.current_count = Some(next_instruction.count - 1);
         self.current_direction = Some(next_instruction.direction);
@@ -158,33 +113,91 @@ impl<'a> Iterator for ExpandedInstructions<'a> {
     }
 }
 
-trait InstructionIterator<'a> {
-    fn instructions(&'a mut self) -> Instructions;
-}
-
-impl<'a> InstructionIterator<'a> for Lines<'a> {
-    fn instructions(&'a mut self) -> Instructions {
-        Instructions { lines: self }
+pub trait Expand: Iterator<Item = Instruction> + Sized {
+    fn expand(self) -> ExpandedInstructions<Self> {
+        ExpandedInstructions {
+            instructions: self,
+            current_count: None,
+            current_direction: None,
+        }
     }
 }
 
-impl<'a> ExpandedInstructions<'a> {
-    fn rope<const N: usize>(&'a mut self) -> Rope<'a, N> {
+impl<I: Iterator<Item = Instruction>> Expand for I {}
+
+impl<I> ExpandedInstructions<I> {
+    pub fn rope<const N: usize>(self) -> Rope<I, N> {
         Rope::new(self)
     }
 }
 
-fn count_seen<const N: usize>(it: Rope<N>) -> usize {
+pub fn count_seen<I: Iterator<Item = Instruction>, const N: usize>(it: Rope<I, N>) -> usize {
     let set: HashSet<Position, RandomState> =
         HashSet::from_iter(it.map(|pos| pos.positions[N - 1]));
     set.len()
 }
 
-pub fn print_solution() {
-    let small_rope_seen = count_seen(INPUT.lines().instructions().expand().rope::<2>());
-    println!("Seen positions for rope with 2 knots: {small_rope_seen}");
-    let large_rope_seen = count_seen(INPUT.lines().instructions().expand().rope::<10>());
-    println!("Seen positions for rope with 10 knots: {large_rope_seen}");
+fn parse_instruction(line_number: usize, line: &str) -> Result<Instruction, ParseError> {
+    let Some((direction, count_text)) = line.trim().split_once(' ') else {
+        return Err(ParseError::new(
+            "expected a direction and a step count like `R 4`",
+            line_number,
+            line,
+        ));
+    };
+
+    let direction = match direction {
+        "R" => Direction::Right,
+        "U" => Direction::Up,
+        "D" => Direction::Down,
+        "L" => Direction::Left,
+        _ => {
+            return Err(ParseError::at(
+                format!("unknown direction `{direction}`"),
+                line_number,
+                line,
+                direction,
+            ))
+        }
+    };
+    let count = parse_number(line_number, line, count_text)?;
+    if count == 0 {
+        return Err(ParseError::at(
+            "step count must be at least 1",
+            line_number,
+            line,
+            count_text,
+        ));
+    }
+
+    Ok(Instruction { count, direction })
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    numbered_lines(input)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| parse_instruction(line_number, line))
+        .collect()
+}
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    type Parsed<'a> = Vec<Instruction>;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        parse_input(input)
+    }
+
+    fn part_one(instructions: &Self::Parsed<'_>) -> usize {
+        count_seen(instructions.iter().copied().expand().rope::<2>())
+    }
+
+    fn part_two(instructions: &Self::Parsed<'_>) -> usize {
+        count_seen(instructions.iter().copied().expand().rope::<10>())
+    }
 }
 
 #[cfg(test)]
@@ -215,8 +228,8 @@ mod test {
     #[test]
     fn test_instructions_iterator() {
         assert_eq!(
-            SAMPLE.lines().instructions().collect::<Vec<Instruction>>(),
-            vec![
+            parse_input(SAMPLE),
+            Ok(vec![
                 Instruction {
                     count: 4,
                     direction: Direction::Right
@@ -249,16 +262,16 @@ mod test {
                     count: 2,
                     direction: Direction::Right
                 },
-            ]
+            ])
         )
     }
 
     #[test]
     fn test_expanded_instructions_iterator() {
         assert_eq!(
-            SHORT_SAMPLE
-                .lines()
-                .instructions()
+            parse_input(SHORT_SAMPLE)
+                .unwrap()
+                .into_iter()
                 .expand()
                 .collect::<Vec<Direction>>(),
             vec![
@@ -276,9 +289,9 @@ mod test {
     #[test]
     fn test_rope_iterator() {
         assert_eq!(
-            SHORT_SAMPLE
-                .lines()
-                .instructions()
+            parse_input(SHORT_SAMPLE)
+                .unwrap()
+                .into_iter()
                 .expand()
                 .rope::<2>()
                 .last(),
@@ -287,7 +300,12 @@ mod test {
             })
         );
         assert_eq!(
-            SAMPLE.lines().instructions().expand().rope::<2>().last(),
+            parse_input(SAMPLE)
+                .unwrap()
+                .into_iter()
+                .expand()
+                .rope::<2>()
+                .last(),
             Some(RopePosition {
                 positions: [Position { x: 2, y: 2 }, Position { x: 1, y: 2 }]
             })
@@ -297,18 +315,49 @@ mod test {
     #[test]
     fn test_count_seen() {
         assert_eq!(
-            count_seen(SAMPLE.lines().instructions().expand().rope::<2>()),
+            count_seen(
+                parse_input(SAMPLE)
+                    .unwrap()
+                    .into_iter()
+                    .expand()
+                    .rope::<2>()
+            ),
             13
         );
 
         assert_eq!(
-            count_seen(SAMPLE.lines().instructions().expand().rope::<10>()),
+            count_seen(
+                parse_input(SAMPLE)
+                    .unwrap()
+                    .into_iter()
+                    .expand()
+                    .rope::<10>()
+            ),
             1
         );
 
         assert_eq!(
-            count_seen(LARGE_SAMPLE.lines().instructions().expand().rope::<10>()),
+            count_seen(
+                parse_input(LARGE_SAMPLE)
+                    .unwrap()
+                    .into_iter()
+                    .expand()
+                    .rope::<10>()
+            ),
             36
         );
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_input("R 4\nX 3").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.message, "unknown direction `X`");
+
+        let error = parse_input("R 4\nU 0").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+
+        let error = parse_input("R4").unwrap_err();
+        assert_eq!(error.line, 1);
+    }
 }
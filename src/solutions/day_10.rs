@@ -1,27 +1,28 @@
-use std::str::Lines;
-
-const INPUT: &str = include_str!("day_10.txt");
+use super::super::answer::Answer;
+use super::super::parse::{numbered_lines, parse_number, ParseError};
+use super::Solution;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Instruction {
+pub enum Instruction {
     Noop,
     Addx(i32),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct Registers {
-    x: i32,
+pub struct Registers {
+    pub x: i32,
 }
 
+/// Simulates the CPU, yielding the registers during every cycle
 #[derive(Debug)]
-struct CommSimulation<'a> {
+pub struct CommSimulation<I> {
     register: Registers,
-    instructions: &'a mut InstructionIterator<'a>,
+    instructions: I,
     pending_instruction: Option<(u32, Instruction)>,
 }
 
-impl<'a> CommSimulation<'a> {
-    fn new(instructions: &'a mut InstructionIterator<'a>) -> CommSimulation<'a> {
+impl<I> CommSimulation<I> {
+    pub fn new(instructions: I) -> CommSimulation<I> {
         CommSimulation {
             register: Registers { x: 1 },
             pending_instruction: None,
@@ -31,22 +32,19 @@ impl<'a> CommSimulation<'a> {
 
     fn apply(&mut self, instruction: Instruction) {
         match instruction {
-            Instruction::Noop => {},
-            Instruction::Addx(count) => self.register.x += count
+            Instruction::Noop => {}
+            Instruction::Addx(count) => self.register.x += count,
         };
     }
-
 }
 
-impl<'a> Iterator for CommSimulation<'a> {
+impl<I: Iterator<Item = Instruction>> Iterator for CommSimulation<I> {
     type Item = Registers;
 
     fn next(&mut self) -> Option<Self::Item> {
         // Nothing executing currently - fetch new instruction
         if self.pending_instruction.is_none() {
-            let Some(next_instruction) = self.instructions.next() else {
-                return None; 
-            };
+            let next_instruction = self.instructions.next()?;
 
             let cycle_count = match next_instruction {
                 Instruction::Noop => 1,
@@ -76,74 +74,98 @@ impl<'a> Iterator for CommSimulation<'a> {
     }
 }
 
-trait CommSimulator<'a> {
-    fn simulate(&'a mut self) -> CommSimulation<'a>;
-}
-
-impl<'a> CommSimulator<'a> for InstructionIterator<'a> {
-    fn simulate(&'a mut self) -> CommSimulation<'a> {
+pub trait CommSimulator: Iterator<Item = Instruction> + Sized {
+    fn simulate(self) -> CommSimulation<Self> {
         CommSimulation::new(self)
     }
 }
 
-#[derive(Debug)]
-struct InstructionIterator<'a> {
-    lines: &'a mut Lines<'a>,
-}
-
-trait Instructions<'a> {
-    fn instructions(&'a mut self) -> InstructionIterator;
-}
-
-impl<'a> Iterator for InstructionIterator<'a> {
-    type Item = Instruction;
-    fn next(&mut self) -> Option<Self::Item> {
-        let Some(instruction) = self.lines.next() else { return None; };
+impl<I: Iterator<Item = Instruction>> CommSimulator for I {}
 
-        match instruction.trim() {
-            "noop" => Some(Instruction::Noop),
-            s if s.starts_with("addx") => {
-                let count = s.split(' ').last().unwrap().parse().unwrap();
-                Some(Instruction::Addx(count))
-            }
-            _ => unreachable!(),
+fn parse_instruction(line_number: usize, line: &str) -> Result<Instruction, ParseError> {
+    match line.trim().split_once(' ') {
+        None if line.trim() == "noop" => Ok(Instruction::Noop),
+        Some(("addx", count)) => Ok(Instruction::Addx(parse_number(
+            line_number,
+            line,
+            count.trim(),
+        )?)),
+        _ => {
+            let opcode = line.split_whitespace().next().unwrap_or(line);
+            Err(ParseError::at(
+                format!("unknown instruction `{}`", line.trim()),
+                line_number,
+                line,
+                opcode,
+            ))
         }
     }
 }
 
-impl<'a> Instructions<'a> for Lines<'a> {
-    fn instructions(&'a mut self) -> InstructionIterator {
-        InstructionIterator { lines: self }
-    }
+pub fn parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    numbered_lines(input)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| parse_instruction(line_number, line))
+        .collect()
 }
 
-fn calculate_signal_strength(input: &str) -> i32 {
+pub fn calculate_signal_strength(instructions: &[Instruction]) -> i32 {
     let target_cycles: [usize; 6] = [20, 60, 100, 140, 180, 220];
-    input.lines().instructions().simulate().enumerate().filter_map(|(idx, registers)| {
-        let target = idx + 1;
-        if !target_cycles.contains(&target) { return None; }
+    instructions
+        .iter()
+        .copied()
+        .simulate()
+        .enumerate()
+        .filter_map(|(idx, registers)| {
+            let target = idx + 1;
+            if !target_cycles.contains(&target) {
+                return None;
+            }
 
-        Some(target as i32 * registers.x)
-    }).sum()
+            Some(target as i32 * registers.x)
+        })
+        .sum()
 }
 
-pub fn print_solution() {
-    let signal_strength = calculate_signal_strength(INPUT);
-    println!("Signal strength: {signal_strength}");
-    let screen: Vec<char> = INPUT.lines().instructions().simulate().enumerate().map(|(idx, registers)| {
-        let to_draw = [idx as i32 - 1, idx as i32, idx as i32 + 1].map(|x| x % 40);
-        if to_draw.contains(&registers.x) {
-            '#'
-        } else {
-            '.'
-        }
-    }).collect();
-    screen.chunks(40).for_each(|line| {
-        for c in line {
-            print!("{}", c);
-        }
-        println!();
-    });
+pub fn render_screen(instructions: &[Instruction]) -> Vec<String> {
+    let screen: Vec<char> = instructions
+        .iter()
+        .copied()
+        .simulate()
+        .enumerate()
+        .map(|(idx, registers)| {
+            let to_draw = [idx as i32 - 1, idx as i32, idx as i32 + 1].map(|x| x % 40);
+            if to_draw.contains(&registers.x) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    screen
+        .chunks(40)
+        .map(|line| line.iter().collect())
+        .collect()
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Parsed<'a> = Vec<Instruction>;
+    type PartOne = i32;
+    type PartTwo = Answer;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        parse_input(input)
+    }
+
+    fn part_one(instructions: &Self::Parsed<'_>) -> i32 {
+        calculate_signal_strength(instructions)
+    }
+
+    fn part_two(instructions: &Self::Parsed<'_>) -> Answer {
+        Answer::Picture(render_screen(instructions))
+    }
 }
 
 #[cfg(test)]
@@ -305,27 +327,63 @@ noop";
     #[test]
     fn test_instructions_iterator() {
         assert_eq!(
-            SAMPLE.lines().instructions().collect::<Vec<Instruction>>(),
-            vec![Instruction::Noop, Instruction::Addx(3), Instruction::Addx(-5)]
-            )
+            parse_input(SAMPLE),
+            Ok(vec![
+                Instruction::Noop,
+                Instruction::Addx(3),
+                Instruction::Addx(-5)
+            ])
+        )
     }
 
     #[test]
     fn test_simulation() {
         assert_eq!(
-            SAMPLE.lines().instructions().simulate().collect::<Vec<Registers>>(),
+            parse_input(SAMPLE)
+                .unwrap()
+                .into_iter()
+                .simulate()
+                .collect::<Vec<Registers>>(),
             vec![
-                Registers {x: 1}, // noop
-                Registers {x: 1}, // addx 3
-                Registers {x: 1}, // -
-                Registers {x: 4}, // addx -5
-                Registers {x: 4}, // -
+                Registers { x: 1 }, // noop
+                Registers { x: 1 }, // addx 3
+                Registers { x: 1 }, // -
+                Registers { x: 4 }, // addx -5
+                Registers { x: 4 }, // -
             ]
         );
     }
 
     #[test]
     fn test_signal_strength() {
-        assert_eq!(calculate_signal_strength(LARGE_SAMPLE), 13140);
+        assert_eq!(
+            calculate_signal_strength(&parse_input(LARGE_SAMPLE).unwrap()),
+            13140
+        );
+    }
+
+    #[test]
+    fn test_render_screen() {
+        assert_eq!(
+            render_screen(&parse_input(LARGE_SAMPLE).unwrap()),
+            vec![
+                "##..##..##..##..##..##..##..##..##..##..",
+                "###...###...###...###...###...###...###.",
+                "####....####....####....####....####....",
+                "#####.....#####.....#####.....#####.....",
+                "######......######......######......####",
+                "#######.......#######.......#######.....",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_input("noop\nmulx 3").unwrap_err();
+        assert_eq!((error.line, error.column, error.len), (2, 1, 4));
+        assert_eq!(error.message, "unknown instruction `mulx 3`");
+
+        let error = parse_input("addx 1\naddx three").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 6));
     }
 }
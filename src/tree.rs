@@ -1,136 +1,545 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::collections::VecDeque;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::marker::PhantomData;
 
-#[derive(Debug, PartialEq)]
-pub enum FSNode {
-    Node(String, HashMap<String, FSNode>),
-    File(String, usize),
+pub mod arena;
+pub mod cleanup;
+pub mod diff;
+pub mod disk;
+pub mod fs;
+pub mod path;
+pub mod query;
+pub mod render;
+pub mod serialize;
+pub mod traverse;
+
+pub use arena::{Arena, NodeId};
+pub use cleanup::{CleanupError, CleanupOptions, Objective, Plan};
+pub use fs::{
+    FSArena, FSData, FSEntry, FSItem, FSKind, FSNode, FSNodeZipper, FileSystem, Metadata, Size,
+    Usage,
+};
+pub use path::FSPath;
+pub use query::{Query, QueryResult};
+pub use render::RenderOptions;
+pub use traverse::Visit;
+
+/// An aggregate over a subtree, such as its total size, which [`Tree`] caches in every node
+/// and keeps up to date as children are inserted or removed. Meant for aggregates that are read
+/// often, other ones are computed on demand with [`Tree::fold`].
+pub trait Measure<T> {
+    type Total: Clone + Debug + PartialEq;
+
+    /// Total of a node on its own, without any children
+    fn of(value: &T) -> Self::Total;
+    fn add(total: &mut Self::Total, child: &Self::Total);
+    fn remove(total: &mut Self::Total, child: &Self::Total);
 }
 
-impl FSNode {
-    pub fn new(data: String) -> FSNode {
-        FSNode::Node(data, HashMap::new())
-    }
+/// The measure of trees that don't need a cached aggregate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoMeasure;
 
-    pub fn name(&self) -> String {
-        match self {
-            FSNode::Node(name, ..) => name.clone(),
-            FSNode::File(name, ..) => name.clone(),
+impl<T> Measure<T> for NoMeasure {
+    type Total = ();
+
+    fn of(_value: &T) {}
+    fn add(_total: &mut (), _child: &()) {}
+    fn remove(_total: &mut (), _child: &()) {}
+}
+
+/// How the children of every node in a [`Tree`] are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChildOrder {
+    /// In the order they were first inserted
+    #[default]
+    Insertion,
+    /// Sorted by their key
+    Name,
+}
+
+type Compare<K> = fn(&K, &K) -> Ordering;
+
+#[derive(Debug)]
+struct Child<K, T, M: Measure<T>> {
+    key: K,
+    tree: Tree<K, T, M>,
+    /// When the child was inserted, so insertion order can be restored after sorting
+    inserted: u64,
+}
+
+/// A tree with a `T` in every node, whose children are addressed by keys of type `K`
+#[derive(Debug)]
+pub struct Tree<K, T, M: Measure<T> = NoMeasure> {
+    value: T,
+    children: Vec<Child<K, T, M>>,
+    /// Position of every child in `children`
+    index: HashMap<K, usize>,
+    /// Set if children are sorted by key instead of kept in insertion order
+    by_name: Option<Compare<K>>,
+    inserted: u64,
+    total: M::Total,
+    measure: PhantomData<M>,
+}
+
+impl<K: Eq + Hash + Clone, T, M: Measure<T>> Tree<K, T, M> {
+    /// A tree without any children (yet)
+    pub fn leaf(value: T) -> Tree<K, T, M> {
+        Tree {
+            total: M::of(&value),
+            value,
+            children: vec![],
+            index: HashMap::new(),
+            by_name: None,
+            inserted: 0,
+            measure: PhantomData,
         }
     }
 
-    pub fn add_child(&mut self, child: FSNode) {
-        if let FSNode::Node(_, children) = self {
-            // children.push(child);
-            children.insert(child.name(), child);
+    pub fn with_children(
+        value: T,
+        children: impl IntoIterator<Item = (K, Tree<K, T, M>)>,
+    ) -> Tree<K, T, M> {
+        let mut tree = Tree::leaf(value);
+        for (key, child) in children {
+            tree.insert_child(key, child);
         }
+        tree
     }
 
-    pub fn zipper(self) -> FSNodeZipper {
-        FSNodeZipper {
-            node: self,
-            parent: None,
-            index_in_parent: String::new(),
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Changes the value of this node. Like with [`Tree::get_mut_at`], the cached totals of its
+    /// ancestors are only refreshed when this is called through [`Tree::update_at`].
+    pub fn update_value(&mut self, f: impl FnOnce(&mut T)) {
+        M::remove(&mut self.total, &M::of(&self.value));
+        f(&mut self.value);
+        M::add(&mut self.total, &M::of(&self.value));
+    }
+
+    /// The cached measure of this whole subtree
+    pub fn total(&self) -> &M::Total {
+        &self.total
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn child_order(&self) -> ChildOrder {
+        match self.by_name {
+            Some(_) => ChildOrder::Name,
+            None => ChildOrder::Insertion,
         }
     }
 
-    pub fn size_below(&self) -> usize {
-        match self {
-            FSNode::File(_, size) => *size,
-            FSNode::Node(_, children) => children.values().map(|c| c.size_below()).sum(),
+    /// Children in the tree's [`ChildOrder`]
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = (&K, &Tree<K, T, M>)> {
+        self.children.iter().map(|child| (&child.key, &child.tree))
+    }
+
+    pub fn child(&self, key: &K) -> Option<&Tree<K, T, M>> {
+        self.index.get(key).map(|&idx| &self.children[idx].tree)
+    }
+
+    fn child_mut(&mut self, key: &K) -> Option<&mut Tree<K, T, M>> {
+        self.index.get(key).map(|&idx| &mut self.children[idx].tree)
+    }
+
+    /// Inserts `child` under `key`, returning the child it replaces. A replacement keeps the
+    /// position of the child it replaces. The child takes on this tree's [`ChildOrder`].
+    pub fn insert_child(&mut self, key: K, mut child: Tree<K, T, M>) -> Option<Tree<K, T, M>> {
+        if child.by_name.is_some() != self.by_name.is_some() {
+            child.apply_order(self.by_name);
+        }
+        M::add(&mut self.total, &child.total);
+        if let Some(&idx) = self.index.get(&key) {
+            let replaced = std::mem::replace(&mut self.children[idx].tree, child);
+            M::remove(&mut self.total, &replaced.total);
+            return Some(replaced);
         }
+
+        let position = match self.by_name {
+            Some(compare) => self
+                .children
+                .partition_point(|other| compare(&other.key, &key) == Ordering::Less),
+            None => self.children.len(),
+        };
+        let inserted = self.inserted;
+        self.inserted += 1;
+        self.index.insert(key.clone(), position);
+        self.children.insert(
+            position,
+            Child {
+                key,
+                tree: child,
+                inserted,
+            },
+        );
+        self.reindex(position + 1);
+        None
+    }
+
+    pub fn remove_child(&mut self, key: &K) -> Option<Tree<K, T, M>> {
+        let position = self.index.remove(key)?;
+        let removed = self.children.remove(position);
+        self.reindex(position);
+        M::remove(&mut self.total, &removed.tree.total);
+        Some(removed.tree)
     }
 
-    pub fn visit_nodes<'a, F>(&'a self, mut callback: F)
-    where
-        F: FnMut(&'a FSNode),
-    {
-        let mut queue = VecDeque::from([self]);
-        while let Some(node) = queue.pop_front() {
-            callback(node);
-            if let FSNode::Node(_, children) = node {
-                for child in children.values() {
-                    queue.push_back(child)
-                }
+    /// Updates the positions of the children from `start` on, which are all in the index already
+    fn reindex(&mut self, start: usize) {
+        for (idx, child) in self.children.iter().enumerate().skip(start) {
+            if let Some(position) = self.index.get_mut(&child.key) {
+                *position = idx;
             }
         }
     }
 
-    pub fn is_directory(&self) -> bool {
-        match self {
-            FSNode::Node(..) => true,
-            FSNode::File(..) => false,
+    /// Takes a child out for a [`Zipper`] without updating the total, together with its
+    /// position. The last child fills the hole and `key` stays in the index, so this and
+    /// [`Tree::put_back`] don't have to shift the other children around.
+    fn take_out(&mut self, key: &K) -> Option<(usize, Child<K, T, M>)> {
+        let position = *self.index.get(key)?;
+        let child = self.children.swap_remove(position);
+        if let Some(moved) = self.children.get(position) {
+            if let Some(index) = self.index.get_mut(&moved.key) {
+                *index = position;
+            }
         }
+        Some((position, child))
     }
-}
 
-#[derive(Debug)]
-pub struct FSNodeZipper {
-    node: FSNode,
-    parent: Option<Box<FSNodeZipper>>,
-    index_in_parent: String,
-}
+    /// Undoes [`Tree::take_out`] without updating the total
+    fn put_back(&mut self, position: usize, child: Child<K, T, M>) {
+        self.children.push(child);
+        let last = self.children.len() - 1;
+        self.children.swap(position, last);
+        if let Some(index) = self.index.get_mut(&self.children[last].key) {
+            *index = last;
+        }
+    }
 
-impl FSNodeZipper {
-    pub fn child(mut self, index: &String) -> Option<FSNodeZipper> {
-        // Remove the specified child from the node's children.
-        // A NodeZipper shouldn't let its users inspect its parent,
-        // since we mutate the parents
-        // to move the focused nodes out of their list of children.
-        if let FSNode::Node(_, ref mut children) = self.node {
-            let child = children.remove(index);
-
-            if let Some(child) = child {
-                // Return a new NodeZipper focused on the specified child.
-                return Some(FSNodeZipper {
-                    node: child,
-                    parent: Some(Box::new(self)),
-                    index_in_parent: index.clone(),
-                });
+    /// Reorders the children of every node below and including this one
+    fn apply_order(&mut self, by_name: Option<Compare<K>>) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            node.by_name = by_name;
+            match by_name {
+                Some(compare) => node.children.sort_by(|a, b| compare(&a.key, &b.key)),
+                None => node.children.sort_by_key(|child| child.inserted),
             }
+            node.reindex(0);
+            stack.extend(node.children.iter_mut().map(|child| &mut child.tree));
         }
+    }
 
-        None
+    /// Follows `keys` down from this node
+    pub fn get_at(&self, keys: &[K]) -> Option<&Tree<K, T, M>> {
+        keys.iter().try_fold(self, |node, key| node.child(key))
     }
 
-    pub fn parent(self) -> FSNodeZipper {
-        // Destructure this NodeZipper
-        let FSNodeZipper {
-            node,
-            parent,
-            index_in_parent,
-        } = self;
+    /// Like [`Tree::get_at`], but mutable. Edits through the returned reference do not update
+    /// the cached totals of its ancestors, use [`Tree::update_at`] for that.
+    pub fn get_mut_at(&mut self, keys: &[K]) -> Option<&mut Tree<K, T, M>> {
+        keys.iter().try_fold(self, |node, key| node.child_mut(key))
+    }
 
-        // Destructure the parent NodeZipper
-        let FSNodeZipper {
-            node: mut parent_node,
-            parent: parent_parent,
-            index_in_parent: parent_index_in_parent,
-        } = *parent.unwrap();
+    /// Applies `f` to the node at `keys` and refreshes the cached totals above it
+    pub fn update_at<R>(
+        &mut self,
+        keys: &[K],
+        f: impl FnOnce(&mut Tree<K, T, M>) -> R,
+    ) -> Option<R> {
+        let Some((key, rest)) = keys.split_first() else {
+            return Some(f(self));
+        };
+        let &idx = self.index.get(key)?;
+        let child = &mut self.children[idx].tree;
+        // Taking the child's total out and putting it back afterwards avoids cloning it
+        M::remove(&mut self.total, &child.total);
+        let result = child.update_at(rest, f);
+        M::add(&mut self.total, &child.total);
+        result
+    }
 
-        if let FSNode::Node(_, ref mut children) = parent_node {
-            children.insert(index_in_parent, node);
+    /// Combines the tree bottom-up: `f` receives every node together with the results of its
+    /// children, in the tree's [`ChildOrder`]
+    pub fn fold<'a, R>(&'a self, mut f: impl FnMut(&'a Tree<K, T, M>, Vec<R>) -> R) -> R {
+        // Post-order without recursion, so deep trees can't overflow the stack. The results of a
+        // node's children are always the last entries in `results` when the node is folded.
+        let mut stack = vec![(self, false)];
+        let mut results = vec![];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                let children = results.split_off(results.len() - node.children.len());
+                results.push(f(node, children));
+            } else {
+                stack.push((node, true));
+                stack.extend(node.children().rev().map(|(_, child)| (child, false)));
+            }
         }
+        results.pop().expect("the root is always folded last")
+    }
 
-        // Return a new NodeZipper focused on the parent.
-        FSNodeZipper {
-            node: parent_node,
-            parent: parent_parent,
-            index_in_parent: parent_index_in_parent,
+    pub fn zipper(self) -> Zipper<K, T, M> {
+        Zipper {
+            node: self,
+            ancestors: vec![],
         }
     }
+}
 
-    pub fn finish(mut self) -> FSNode {
-        while self.parent.is_some() {
-            self = self.parent();
+impl<K: Eq + Hash + Clone + Ord, T, M: Measure<T>> Tree<K, T, M> {
+    /// Switches every node of the tree, and everything inserted later, to `order`
+    pub fn set_child_order(&mut self, order: ChildOrder) {
+        self.apply_order(match order {
+            ChildOrder::Insertion => None,
+            ChildOrder::Name => Some(K::cmp),
+        });
+    }
+}
+
+/// Trees are equal if they have the same values in the same places, regardless of the order of
+/// children. Their totals follow from that.
+impl<K: Eq + Hash + Clone, T: PartialEq, M: Measure<T>> PartialEq for Tree<K, T, M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+            && self.children.len() == other.children.len()
+            && self
+                .children()
+                .all(|(key, child)| other.child(key) == Some(child))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NavigationError<K> {
+    /// Tried to move to the parent of the root
+    AboveRoot,
+    NoSuchChild(K),
+    /// Tried to move into a node that can't have children
+    NotADirectory(K),
+}
+
+impl<K: fmt::Display> fmt::Display for NavigationError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NavigationError::AboveRoot => write!(f, "already at the root"),
+            NavigationError::NoSuchChild(key) => write!(f, "`{key}` does not exist"),
+            NavigationError::NotADirectory(key) => write!(f, "`{key}` is not a directory"),
         }
+    }
+}
 
-        self.node
+impl<K: fmt::Debug + fmt::Display> std::error::Error for NavigationError<K> {}
+
+/// Moves through a tree, allowing edits at the focused node.
+/// Failed moves return an error and leave the focus where it was.
+#[derive(Debug)]
+pub struct Zipper<K, T, M: Measure<T> = NoMeasure> {
+    node: Tree<K, T, M>,
+    /// Every node above the focused one, each with the child on the way down taken out of its
+    /// children, together with what is needed to put that child back in its place. Those nodes
+    /// are out of reach until the zipper moves back up, so their children can be out of order
+    /// in the meantime.
+    ancestors: Vec<(Tree<K, T, M>, Hole<K>)>,
+}
+
+/// Where the focused node was taken out of its parent
+#[derive(Debug)]
+struct Hole<K> {
+    key: K,
+    position: usize,
+    inserted: u64,
+}
+
+impl<K: Eq + Hash + Clone, T, M: Measure<T>> Zipper<K, T, M> {
+    pub fn node(&self) -> &Tree<K, T, M> {
+        &self.node
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.ancestors.is_empty()
+    }
+
+    /// Keys leading from the root to the focused node
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.ancestors.iter().map(|(_, hole)| &hole.key)
+    }
+
+    pub fn child(&mut self, key: &K) -> Result<&mut Self, NavigationError<K>> {
+        // Take the child out of the focused node's children, it is put back when moving up again
+        let (position, child) = self
+            .node
+            .take_out(key)
+            .ok_or_else(|| NavigationError::NoSuchChild(key.clone()))?;
+        M::remove(&mut self.node.total, &child.tree.total);
+        let hole = Hole {
+            key: child.key,
+            position,
+            inserted: child.inserted,
+        };
+        self.ancestors
+            .push((std::mem::replace(&mut self.node, child.tree), hole));
+        Ok(self)
+    }
+
+    pub fn parent(&mut self) -> Result<&mut Self, NavigationError<K>> {
+        let (parent, hole) = self.ancestors.pop().ok_or(NavigationError::AboveRoot)?;
+        let child = std::mem::replace(&mut self.node, parent);
+        M::add(&mut self.node.total, &child.total);
+        self.node.put_back(
+            hole.position,
+            Child {
+                key: hole.key,
+                tree: child,
+                inserted: hole.inserted,
+            },
+        );
+        Ok(self)
+    }
+
+    pub fn root(&mut self) -> &mut Self {
+        while self.parent().is_ok() {}
+        self
     }
 
-    pub fn add_child(mut self, child: FSNode) -> FSNodeZipper {
-        self.node.add_child(child);
+    pub fn finish(mut self) -> Tree<K, T, M> {
+        self.root();
+        self.node
+    }
 
+    pub fn insert_child(&mut self, key: K, child: Tree<K, T, M>) -> &mut Self {
+        self.node.insert_child(key, child);
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Counts the nodes of a subtree
+    #[derive(Debug, PartialEq)]
+    struct Count;
+
+    impl<T> Measure<T> for Count {
+        type Total = usize;
+
+        fn of(_value: &T) -> usize {
+            1
+        }
+        fn add(total: &mut usize, child: &usize) {
+            *total += child;
+        }
+        fn remove(total: &mut usize, child: &usize) {
+            *total -= child;
+        }
+    }
+
+    fn tree() -> Tree<u8, i32, Count> {
+        Tree::with_children(
+            1,
+            [
+                (0, Tree::leaf(2)),
+                (1, Tree::with_children(3, [(0, Tree::leaf(4))])),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_fold() {
+        let tree = tree();
+        assert_eq!(
+            tree.fold(|node, children: Vec<i32>| node.value() + children.iter().sum::<i32>()),
+            10
+        );
+        assert_eq!(
+            tree.fold(|_, children: Vec<usize>| 1 + children.into_iter().max().unwrap_or(0)),
+            3
+        );
+    }
+
+    #[test]
+    fn test_measure_follows_edits() {
+        let mut tree = tree();
+        assert_eq!(*tree.total(), 4);
+        tree.insert_child(0, Tree::leaf(5));
+        assert_eq!(*tree.total(), 4);
+
+        let mut zipper = tree.zipper();
+        zipper.child(&1).unwrap().insert_child(1, Tree::leaf(6));
+        assert_eq!(zipper.keys().collect::<Vec<_>>(), vec![&1]);
+        let mut tree = zipper.finish();
+        assert_eq!(*tree.total(), 5);
+        assert_eq!(*tree.get_at(&[1]).unwrap().total(), 3);
+
+        tree.update_at(&[1], |node| node.remove_child(&0));
+        assert_eq!(*tree.total(), 4);
+        assert_eq!(*tree.value(), 1);
+        // The cached total matches counting the nodes from scratch
+        assert_eq!(
+            tree.fold(|_, children: Vec<usize>| 1 + children.iter().sum::<usize>()),
+            *tree.total()
+        );
+    }
+
+    #[test]
+    fn test_child_order() {
+        let keys = |tree: &Tree<u8, i32, Count>| -> Vec<u8> {
+            tree.children().map(|(&key, _)| key).collect()
+        };
+        let mut tree = Tree::with_children(0, [(3, Tree::leaf(1)), (1, Tree::leaf(2))]);
+        tree.insert_child(2, Tree::leaf(3));
+        assert_eq!(keys(&tree), vec![3, 1, 2]);
+
+        // Moving through a zipper puts children back where they were
+        let mut zipper = tree.zipper();
+        zipper.child(&1).unwrap().insert_child(9, Tree::leaf(4));
+        let mut tree = zipper.finish();
+        assert_eq!(keys(&tree), vec![3, 1, 2]);
+        assert_eq!(*tree.total(), 5);
+        assert_eq!(*tree.child(&2).unwrap().value(), 3);
+        assert_eq!(*tree.get_at(&[1, 9]).unwrap().value(), 4);
+
+        tree.set_child_order(ChildOrder::Name);
+        tree.insert_child(
+            0,
+            Tree::with_children(5, [(2, Tree::leaf(6)), (1, Tree::leaf(7))]),
+        );
+        assert_eq!(keys(&tree), vec![0, 1, 2, 3]);
+        assert_eq!(keys(tree.child(&0).unwrap()), vec![1, 2]);
+        assert_eq!(
+            tree.fold(|node, children: Vec<Vec<i32>>| {
+                let mut values = vec![*node.value()];
+                values.extend(children.into_iter().flatten());
+                values
+            }),
+            vec![0, 5, 7, 6, 2, 4, 3, 1]
+        );
+
+        tree.set_child_order(ChildOrder::Insertion);
+        assert_eq!(tree.child_order(), ChildOrder::Insertion);
+        assert_eq!(keys(&tree), vec![3, 1, 2, 0]);
+        assert_eq!(keys(tree.child(&0).unwrap()), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_failed_navigation_keeps_focus() {
+        let mut zipper = tree().zipper();
+        assert_eq!(zipper.parent().unwrap_err(), NavigationError::AboveRoot);
+        zipper.child(&1).unwrap();
+        assert_eq!(
+            zipper.child(&7).unwrap_err(),
+            NavigationError::NoSuchChild(7)
+        );
+        assert_eq!(*zipper.node().value(), 3);
+        assert_eq!(zipper.finish(), tree());
+    }
+}
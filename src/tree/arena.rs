@@ -0,0 +1,565 @@
+use super::{Child, ChildOrder, Compare, Measure, NoMeasure, Tree};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// Refers to a node of an [`Arena`]. Once that node is removed, the id may be reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A key stored once in the arena, no matter how many nodes use it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Name(u32);
+
+#[derive(Debug)]
+struct Slot<T, M: Measure<T>> {
+    value: T,
+    /// `None` for a root without a key
+    name: Option<Name>,
+    parent: Option<NodeId>,
+    /// In the arena's [`ChildOrder`]
+    children: Vec<NodeId>,
+    /// When the node was inserted into its parent, like in a [`Tree`]
+    inserted: u64,
+    /// The stamp for the next child inserted into this node
+    next: u64,
+    total: M::Total,
+}
+
+/// A [`Tree`] stored in a single vector for trees with millions of nodes. Every node knows its
+/// parent, so moving up and editing deep inside the tree need no [`Zipper`](super::Zipper).
+/// Children are kept in the [`ChildOrder`] of the tree the arena was made from.
+///
+/// Totals are kept up to date by adding and removing the change along the path to the root,
+/// which assumes the order in which a [`Measure`] combines totals doesn't matter.
+#[derive(Debug)]
+pub struct Arena<K, T, M: Measure<T> = NoMeasure> {
+    slots: Vec<Option<Slot<T, M>>>,
+    /// Slots of removed nodes, which are reused first
+    free: Vec<NodeId>,
+    keys: Vec<K>,
+    names: HashMap<K, Name>,
+    /// The child of a node under a name
+    edges: HashMap<(NodeId, Name), NodeId>,
+    /// Set if children are sorted by key instead of kept in insertion order
+    by_name: Option<Compare<K>>,
+}
+
+impl<K: Eq + Hash + Clone, T, M: Measure<T>> Arena<K, T, M> {
+    /// An arena with only a root
+    pub fn new(value: T) -> Arena<K, T, M> {
+        Arena {
+            slots: vec![Some(Slot {
+                total: M::of(&value),
+                value,
+                name: None,
+                parent: None,
+                children: vec![],
+                inserted: 0,
+                next: 0,
+            })],
+            free: vec![],
+            keys: vec![],
+            names: HashMap::new(),
+            edges: HashMap::new(),
+            by_name: None,
+        }
+    }
+
+    /// The root, which is never removed
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Number of nodes, including the root
+    pub fn node_count(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Whether `id` refers to a node that hasn't been removed
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.slots.get(id.index()).is_some_and(Option::is_some)
+    }
+
+    fn slot(&self, id: NodeId) -> &Slot<T, M> {
+        self.slots[id.index()]
+            .as_ref()
+            .expect("the node has been removed")
+    }
+
+    fn slot_mut(&mut self, id: NodeId) -> &mut Slot<T, M> {
+        self.slots[id.index()]
+            .as_mut()
+            .expect("the node has been removed")
+    }
+
+    pub fn value(&self, id: NodeId) -> &T {
+        &self.slot(id).value
+    }
+
+    /// Changes the value of a node and refreshes the totals up to the root
+    pub fn update_value(&mut self, id: NodeId, f: impl FnOnce(&mut T)) {
+        let slot = self.slot_mut(id);
+        let before = M::of(&slot.value);
+        f(&mut slot.value);
+        let after = M::of(&slot.value);
+        self.adjust_totals(id, |total| {
+            M::remove(total, &before);
+            M::add(total, &after);
+        });
+    }
+
+    /// The cached measure of the subtree at `id`
+    pub fn total(&self, id: NodeId) -> &M::Total {
+        &self.slot(id).total
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).parent
+    }
+
+    /// The key `id` is stored under in its parent. The root only has one if the arena was made
+    /// with [`Arena::from_tree_with`].
+    pub fn key(&self, id: NodeId) -> Option<&K> {
+        self.slot(id).name.map(|name| &self.keys[name.0 as usize])
+    }
+
+    pub fn is_leaf(&self, id: NodeId) -> bool {
+        self.slot(id).children.is_empty()
+    }
+
+    pub fn child_order(&self) -> ChildOrder {
+        match self.by_name {
+            Some(_) => ChildOrder::Name,
+            None => ChildOrder::Insertion,
+        }
+    }
+
+    /// Children in the arena's [`ChildOrder`]
+    pub fn children(&self, id: NodeId) -> impl DoubleEndedIterator<Item = (&K, NodeId)> + '_ {
+        self.slot(id)
+            .children
+            .iter()
+            .map(|&child| (self.key(child).expect("children have keys"), child))
+    }
+
+    pub fn child(&self, id: NodeId, key: &K) -> Option<NodeId> {
+        let name = self.names.get(key)?;
+        self.edges.get(&(id, *name)).copied()
+    }
+
+    /// Follows `keys` down from `id`
+    pub fn get_at(&self, id: NodeId, keys: &[K]) -> Option<NodeId> {
+        keys.iter().try_fold(id, |node, key| self.child(node, key))
+    }
+
+    /// Keys leading from the root to `id`
+    pub fn keys_of(&self, id: NodeId) -> Vec<&K> {
+        let mut keys = vec![];
+        let mut node = id;
+        while let Some(parent) = self.parent(node) {
+            keys.push(self.key(node).expect("children have keys"));
+            node = parent;
+        }
+        keys.reverse();
+        keys
+    }
+
+    /// `id` and every node below it, parents before their children
+    pub fn iter_preorder(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut stack = vec![id];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(self.slot(node).children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Inserts `child` with everything below it under `key`, returning its id. A child already
+    /// under `key` is dropped and the new one takes its position.
+    pub fn insert_child(&mut self, parent: NodeId, key: K, child: Tree<K, T, M>) -> NodeId {
+        self.insert_child_with(parent, key, child, |value| value)
+    }
+
+    /// Like [`Arena::insert_child`], but stores `strip(value)` for every value of `child`
+    pub fn insert_child_with<U>(
+        &mut self,
+        parent: NodeId,
+        key: K,
+        child: Tree<K, U, M>,
+        mut strip: impl FnMut(U) -> T,
+    ) -> NodeId
+    where
+        M: Measure<U, Total = <M as Measure<T>>::Total>,
+    {
+        let inserted = match self.child(parent, &key) {
+            Some(replaced) => {
+                let inserted = self.slot(replaced).inserted;
+                self.unlink(replaced);
+                self.take_tree(replaced, &mut |_, value| value);
+                inserted
+            }
+            None => {
+                let slot = self.slot_mut(parent);
+                slot.next += 1;
+                slot.next - 1
+            }
+        };
+        let total = child.total.clone();
+        let id = self.attach(parent, key, inserted, child, &mut strip);
+        self.adjust_totals(parent, |sum| <M as Measure<T>>::add(sum, &total));
+        id
+    }
+
+    /// Takes the child under `key` with everything below it out of the arena
+    pub fn remove_child(&mut self, parent: NodeId, key: &K) -> Option<Tree<K, T, M>> {
+        let id = self.child(parent, key)?;
+        self.unlink(id);
+        Some(self.take_tree(id, &mut |_, value| value))
+    }
+
+    /// Turns the arena back into a [`Tree`] with the same [`ChildOrder`]
+    pub fn into_tree(self) -> Tree<K, T, M> {
+        self.into_tree_with(|_, value| value)
+    }
+
+    /// Like [`Arena::into_tree`], but turns every value into `dress(key, value)`, where `key`
+    /// is the one the node is stored under
+    pub fn into_tree_with<U>(mut self, mut dress: impl FnMut(Option<&K>, T) -> U) -> Tree<K, U, M>
+    where
+        M: Measure<U, Total = <M as Measure<T>>::Total>,
+    {
+        self.take_tree(NodeId(0), &mut dress)
+    }
+
+    /// Like [`Arena::from`], but stores `strip(value)` for every value of `tree` and gives the
+    /// root `root_key`. Values that repeat their key can leave it to the arena this way, which
+    /// keeps every distinct key once.
+    pub fn from_tree_with<U>(
+        tree: Tree<K, U, M>,
+        root_key: Option<K>,
+        mut strip: impl FnMut(U) -> T,
+    ) -> Arena<K, T, M>
+    where
+        M: Measure<U, Total = <M as Measure<T>>::Total>,
+    {
+        let Tree {
+            value,
+            children,
+            by_name,
+            inserted,
+            total,
+            ..
+        } = tree;
+        let mut arena = Arena::new(strip(value));
+        arena.by_name = by_name;
+        let root = arena.root();
+        let name = root_key.map(|key| arena.intern(key));
+        let slot = arena.slot_mut(root);
+        slot.name = name;
+        slot.next = inserted;
+        slot.total = total;
+        for child in children {
+            arena.attach(root, child.key, child.inserted, child.tree, &mut strip);
+        }
+        arena
+    }
+
+    /// Applies `f` to the total of `id` and of each of its ancestors
+    fn adjust_totals(&mut self, id: NodeId, mut f: impl FnMut(&mut M::Total)) {
+        let mut node = Some(id);
+        while let Some(id) = node {
+            let slot = self.slot_mut(id);
+            f(&mut slot.total);
+            node = slot.parent;
+        }
+    }
+
+    fn intern(&mut self, key: K) -> Name {
+        if let Some(&name) = self.names.get(&key) {
+            return name;
+        }
+        let name = Name(u32::try_from(self.keys.len()).expect("too many distinct keys"));
+        self.keys.push(key.clone());
+        self.names.insert(key, name);
+        name
+    }
+
+    fn allocate(&mut self, slot: Slot<T, M>) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                self.slots[id.index()] = Some(slot);
+                id
+            }
+            None => {
+                let id = NodeId(u32::try_from(self.slots.len()).expect("too many nodes"));
+                self.slots.push(Some(slot));
+                id
+            }
+        }
+    }
+
+    /// Moves `tree` into the arena as a child of `parent` inserted at `inserted`, keeping its
+    /// cached totals. The totals above `parent` are left to the caller.
+    fn attach<U>(
+        &mut self,
+        parent: NodeId,
+        key: K,
+        inserted: u64,
+        tree: Tree<K, U, M>,
+        strip: &mut impl FnMut(U) -> T,
+    ) -> NodeId
+    where
+        M: Measure<U, Total = <M as Measure<T>>::Total>,
+    {
+        let mut top = None;
+        let mut stack = vec![(
+            parent,
+            Child {
+                key,
+                tree,
+                inserted,
+            },
+        )];
+        while let Some((parent, child)) = stack.pop() {
+            let Tree {
+                value,
+                children,
+                inserted: next,
+                total,
+                ..
+            } = child.tree;
+            let name = self.intern(child.key);
+            let id = self.allocate(Slot {
+                value: strip(value),
+                name: Some(name),
+                parent: Some(parent),
+                children: vec![],
+                inserted: child.inserted,
+                next,
+                total,
+            });
+            self.edges.insert((parent, name), id);
+            self.place(parent, id);
+            top.get_or_insert(id);
+            stack.extend(children.into_iter().rev().map(|child| (id, child)));
+        }
+        top.expect("the tree itself is attached first")
+    }
+
+    /// Adds `id` to the children of `parent`, at its position in the arena's order
+    fn place(&mut self, parent: NodeId, id: NodeId) {
+        let children = &self.slot(parent).children;
+        let position = match self.by_name {
+            Some(compare) => {
+                let key = self.key(id).expect("children have keys");
+                children.partition_point(|&other| {
+                    compare(self.key(other).expect("children have keys"), key) == Ordering::Less
+                })
+            }
+            None => {
+                let inserted = self.slot(id).inserted;
+                children.partition_point(|&other| self.slot(other).inserted < inserted)
+            }
+        };
+        self.slot_mut(parent).children.insert(position, id);
+    }
+
+    /// Takes `id` out of its parent's children and its total out of its ancestors
+    fn unlink(&mut self, id: NodeId) {
+        let parent = self.parent(id).expect("the root stays in the arena");
+        let total = self.total(id).clone();
+        self.adjust_totals(parent, |sum| M::remove(sum, &total));
+        self.slot_mut(parent).children.retain(|&child| child != id);
+    }
+
+    /// Moves the subtree at `id` out of the arena, freeing its slots. The cached totals are
+    /// moved along instead of being added up again.
+    fn take_tree<U>(
+        &mut self,
+        id: NodeId,
+        dress: &mut impl FnMut(Option<&K>, T) -> U,
+    ) -> Tree<K, U, M>
+    where
+        M: Measure<U, Total = <M as Measure<T>>::Total>,
+    {
+        let order: Vec<NodeId> = self.iter_preorder(id).collect();
+        // Going backwards, every node comes right after its children, the first one on top
+        let mut taken: Vec<Child<K, U, M>> = vec![];
+        for node in order.into_iter().rev() {
+            let slot = self.slots[node.index()]
+                .take()
+                .expect("the subtree is in the arena");
+            self.free.push(node);
+            if let (Some(parent), Some(name)) = (slot.parent, slot.name) {
+                self.edges.remove(&(parent, name));
+            }
+            let first = taken.len() - slot.children.len();
+            let children: Vec<Child<K, U, M>> = taken.drain(first..).rev().collect();
+            let index = children
+                .iter()
+                .enumerate()
+                .map(|(position, child)| (child.key.clone(), position))
+                .collect();
+            let key = slot.name.map(|name| &self.keys[name.0 as usize]);
+            let tree = Tree {
+                value: dress(key, slot.value),
+                children,
+                index,
+                by_name: self.by_name,
+                inserted: slot.next,
+                total: slot.total,
+                measure: PhantomData,
+            };
+            if node == id {
+                return tree;
+            }
+            taken.push(Child {
+                key: key.expect("children have keys").clone(),
+                tree,
+                inserted: slot.inserted,
+            });
+        }
+        unreachable!("the subtree is taken last")
+    }
+}
+
+impl<K: Eq + Hash + Clone, T, M: Measure<T>> From<Tree<K, T, M>> for Arena<K, T, M> {
+    fn from(tree: Tree<K, T, M>) -> Arena<K, T, M> {
+        Arena::from_tree_with(tree, None, |value| value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Counts the nodes of every subtree
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Count;
+
+    impl Measure<char> for Count {
+        type Total = usize;
+
+        fn of(_value: &char) -> usize {
+            1
+        }
+        fn add(total: &mut usize, child: &usize) {
+            *total += child;
+        }
+        fn remove(total: &mut usize, child: &usize) {
+            *total -= child;
+        }
+    }
+
+    type Node = Tree<&'static str, char, Count>;
+
+    fn leaf(value: char) -> Node {
+        Tree::leaf(value)
+    }
+
+    /// `r` with `a` (holding `x` and `y`) and `b` (holding another `x`)
+    fn tree() -> Node {
+        Tree::with_children(
+            'r',
+            [
+                (
+                    "a",
+                    Tree::with_children('a', [("x", leaf('x')), ("y", leaf('y'))]),
+                ),
+                ("b", Tree::with_children('b', [("x", leaf('z'))])),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_navigation() {
+        let arena = Arena::from(tree());
+        let root = arena.root();
+        assert_eq!((arena.node_count(), arena.keys.len()), (6, 4));
+        assert_eq!(*arena.total(root), 6);
+
+        let x = arena.get_at(root, &["b", "x"]).unwrap();
+        assert_eq!(*arena.value(x), 'z');
+        assert_eq!(arena.keys_of(x), vec![&"b", &"x"]);
+        let b = arena.parent(x).unwrap();
+        assert_eq!(arena.key(b), Some(&"b"));
+        assert_eq!(arena.parent(b), Some(root));
+        assert_eq!(arena.parent(root), None);
+        assert_eq!(arena.get_at(root, &["a", "z"]), None);
+
+        let values: Vec<char> = arena
+            .iter_preorder(root)
+            .map(|id| *arena.value(id))
+            .collect();
+        assert_eq!(values, vec!['r', 'a', 'x', 'y', 'b', 'z']);
+        assert_eq!(arena.into_tree(), tree());
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut arena = Arena::from(tree());
+        let root = arena.root();
+        let a = arena.child(root, &"a").unwrap();
+        let c = arena.insert_child(a, "c", Tree::with_children('c', [("d", leaf('d'))]));
+        assert_eq!((*arena.total(a), *arena.total(root)), (5, 8));
+
+        let removed = arena.remove_child(root, &"b").unwrap();
+        assert_eq!(removed, Tree::with_children('b', [("x", leaf('z'))]));
+        assert_eq!(*arena.total(root), 6);
+        assert_eq!(arena.node_count(), 6);
+
+        // Replacing keeps the position, and removed slots are reused
+        arena.insert_child(a, "x", leaf('w'));
+        assert_eq!(arena.slots.len(), 8);
+        let children: Vec<&str> = arena.children(a).map(|(key, _)| *key).collect();
+        assert_eq!(children, vec!["x", "y", "c"]);
+        assert!(arena.contains(c));
+
+        arena.update_value(c, |value| *value = 'e');
+        assert_eq!(*arena.value(c), 'e');
+        assert_eq!(*arena.total(root), 6);
+        assert_eq!(arena.remove_child(root, &"b"), None);
+    }
+
+    #[test]
+    fn test_child_order() {
+        let a = Tree::with_children('a', [("y", leaf('y')), ("x", leaf('x'))]);
+        let mut tree: Node = Tree::with_children('r', [("b", leaf('b')), ("a", a)]);
+        tree.set_child_order(ChildOrder::Name);
+        let mut arena = Arena::from(tree);
+        assert_eq!(arena.child_order(), ChildOrder::Name);
+        let root = arena.root();
+        arena.insert_child(
+            root,
+            "ab",
+            Tree::with_children('c', [("z", leaf('z')), ("w", leaf('w'))]),
+        );
+        let keys: Vec<&str> = arena.children(root).map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec!["a", "ab", "b"]);
+
+        let keys = |tree: &Node, path: &[&'static str]| -> Vec<&str> {
+            let node = tree.get_at(path).unwrap();
+            node.children().map(|(key, _)| *key).collect()
+        };
+        let mut tree = arena.into_tree();
+        assert_eq!(tree.child_order(), ChildOrder::Name);
+        assert_eq!(keys(&tree, &[]), vec!["a", "ab", "b"]);
+        assert_eq!(keys(&tree, &["a"]), vec!["x", "y"]);
+        assert_eq!(keys(&tree, &["ab"]), vec!["w", "z"]);
+
+        // The insertion order survives the round trip as well
+        tree.set_child_order(ChildOrder::Insertion);
+        assert_eq!(keys(&tree, &[]), vec!["b", "a", "ab"]);
+        assert_eq!(keys(&tree, &["a"]), vec!["y", "x"]);
+        assert_eq!(keys(&tree, &["ab"]), vec!["z", "w"]);
+    }
+}
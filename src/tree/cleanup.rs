@@ -0,0 +1,647 @@
+use super::{FSArena, FSKind, FSNode, FSPath, FileSystem};
+use clap::ValueEnum;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::rc::Rc;
+
+/// Plans that don't free enough yet are grouped by freed bytes into this many buckets, keeping
+/// only the best few plans of each. That bounds the work per directory.
+const BUCKETS: usize = 1024;
+
+/// Plans kept per bucket that differ in which hard-linked data they have partly deleted
+const VARIANTS: usize = 4;
+
+/// What makes one cleanup plan better than another, the other measure breaks ties
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Objective {
+    #[default]
+    FewestDeletions,
+    LeastBytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanupOptions {
+    /// Size of the whole disk
+    pub capacity: usize,
+    /// Space that has to be free after the cleanup
+    pub required: usize,
+    pub objective: Objective,
+    /// Absolute paths that must survive, together with everything inside them
+    pub protected: Vec<FSPath>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CleanupError {
+    /// The tree takes up more than the disk can hold
+    OverCapacity { used: usize, capacity: usize },
+    /// Even the largest allowed deletions don't free `needed` bytes
+    Impossible { needed: usize, available: usize },
+}
+
+impl fmt::Display for CleanupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CleanupError::OverCapacity { used, capacity } => {
+                write!(
+                    f,
+                    "{used} bytes are used, but the disk only holds {capacity}"
+                )
+            }
+            CleanupError::Impossible { needed, available } => write!(
+                f,
+                "{needed} bytes have to be deleted, but at most {available} can be"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CleanupError {}
+
+/// Directories to delete, which never contain each other. Hard-linked data is freed once all of
+/// its links are deleted, whether by one of the deletions or by several of them together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub options: CleanupOptions,
+    pub used: usize,
+    /// Bytes that had to be deleted at least
+    pub needed: usize,
+    /// Directories with the space deleting each of them on its own would free
+    pub deletions: Vec<(FSPath, usize)>,
+    /// Everything the deletions free together, which can be more than their sizes add up to when
+    /// they hold different links to the same data
+    pub freed: usize,
+    /// Whether every possible plan was considered. With a lot of bytes to free, plans that free
+    /// almost the same amount are merged, and only a few ways of partly deleting hard-linked data
+    /// are followed, so a slightly better plan may have been missed.
+    pub exhaustive: bool,
+}
+
+impl Plan {
+    /// Describes the situation, the chosen deletions and their effect
+    pub fn explain(&self) -> String {
+        let CleanupOptions {
+            capacity, required, ..
+        } = self.options;
+        let mut output = format!(
+            "{} of {capacity} bytes used, {required} must be free, ",
+            self.used
+        );
+        if self.needed == 0 {
+            output.push_str("so nothing has to be deleted\n");
+        } else {
+            output.push_str(&format!("so at least {} have to be deleted\n", self.needed));
+        }
+        if !self.options.protected.is_empty() {
+            let protected: Vec<String> = self
+                .options
+                .protected
+                .iter()
+                .map(|p| p.to_string())
+                .collect();
+            output.push_str(&format!("keeping {}\n", protected.join(", ")));
+        }
+        for (path, size) in &self.deletions {
+            output.push_str(&format!("delete {path} ({size})\n"));
+        }
+        let objective = match self.options.objective {
+            Objective::FewestDeletions => "fewest deletions",
+            Objective::LeastBytes => "least bytes deleted",
+        };
+        output.push_str(&format!(
+            "frees {} in {} deletion(s), leaving {} free, chosen for {objective}\n",
+            self.freed,
+            self.deletions.len(),
+            capacity - self.used + self.freed
+        ));
+        if !self.exhaustive {
+            output.push_str("similar plans were merged, so a better plan may exist\n");
+        }
+        output
+    }
+}
+
+/// A node of the tree in pre-order, as far as the planner needs to know about it
+enum Seen {
+    Directory {
+        depth: usize,
+        path: FSPath,
+        size: usize,
+    },
+    Link {
+        depth: usize,
+        id: u64,
+        size: usize,
+    },
+}
+
+/// A directory that may be deleted
+struct Candidate {
+    path: FSPath,
+    /// What deleting only this directory frees
+    freed: usize,
+    deletable: bool,
+    /// Position of the first candidate that isn't inside this one
+    end: usize,
+    /// Hard-linked data with links both inside and outside of this directory, by its index in
+    /// `Planner::shared`, with the number of links inside
+    partly: Vec<(usize, usize)>,
+}
+
+/// Data with several hard links
+struct Shared {
+    size: usize,
+    links: usize,
+    /// Position of the last candidate holding one of the links, no later deletion frees any
+    last: usize,
+}
+
+/// Lists the candidates in pre-order, so everything inside a candidate comes right after it
+fn candidates(
+    nodes: impl IntoIterator<Item = Seen>,
+    protected: &[FSPath],
+) -> (Vec<Candidate>, Vec<Shared>) {
+    let mut candidates: Vec<Candidate> = vec![];
+    let mut shared: Vec<Shared> = vec![];
+    let mut ids: HashMap<u64, usize> = HashMap::new();
+    // Links to each piece of shared data inside each candidate
+    let mut inside: Vec<HashMap<usize, usize>> = vec![];
+    // Candidates containing the current node with their depth, innermost last
+    let mut open: Vec<(usize, usize)> = vec![];
+    for seen in nodes {
+        let (Seen::Directory { depth, .. } | Seen::Link { depth, .. }) = seen;
+        while let Some(&(position, _)) = open.last().filter(|&&(_, above)| above >= depth) {
+            candidates[position].end = candidates.len();
+            open.pop();
+        }
+        match seen {
+            Seen::Directory { path, size, .. } => {
+                let deletable = !protected
+                    .iter()
+                    .any(|p| path.starts_with(p) || p.starts_with(&path));
+                open.push((candidates.len(), depth));
+                candidates.push(Candidate {
+                    path,
+                    freed: size,
+                    deletable,
+                    end: 0,
+                    partly: vec![],
+                });
+                inside.push(HashMap::new());
+            }
+            Seen::Link { id, size, .. } => {
+                let next = shared.len();
+                let data = *ids.entry(id).or_insert(next);
+                if data == next {
+                    shared.push(Shared {
+                        size,
+                        links: 0,
+                        last: 0,
+                    });
+                }
+                shared[data].links += 1;
+                if let Some(&(parent, _)) = open.last() {
+                    shared[data].last = shared[data].last.max(parent);
+                }
+                for &(position, _) in &open {
+                    *inside[position].entry(data).or_default() += 1;
+                }
+            }
+        }
+    }
+    for (position, _) in open {
+        candidates[position].end = candidates.len();
+    }
+    for (candidate, links) in candidates.iter_mut().zip(inside) {
+        for (data, count) in links {
+            if count < shared[data].links {
+                // Other links keep the data around
+                candidate.freed = candidate.freed.saturating_sub(shared[data].size);
+                candidate.partly.push((data, count));
+            }
+        }
+        candidate.partly.sort();
+    }
+    (candidates, shared)
+}
+
+/// One deletion of a plan. Plans that start out the same share these.
+struct Deletion {
+    candidate: usize,
+    previous: Option<Rc<Deletion>>,
+}
+
+impl Drop for Deletion {
+    /// Drops long chains of deletions without recursing
+    fn drop(&mut self) {
+        let mut previous = self.previous.take();
+        while let Some(deletion) = previous {
+            previous = Rc::try_unwrap(deletion)
+                .ok()
+                .and_then(|mut deletion| deletion.previous.take());
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Choice {
+    freed: usize,
+    deletions: usize,
+    /// Links deleted so far of shared data that other links still keep, by its index in
+    /// `Planner::shared`, sorted
+    pending: Vec<(usize, usize)>,
+    last: Option<Rc<Deletion>>,
+}
+
+impl Choice {
+    fn compare(&self, other: &Choice, objective: Objective) -> Ordering {
+        let by_count = self.deletions.cmp(&other.deletions);
+        let by_bytes = self.freed.cmp(&other.freed);
+        match objective {
+            Objective::FewestDeletions => by_count.then(by_bytes),
+            Objective::LeastBytes => by_bytes.then(by_count),
+        }
+    }
+}
+
+/// Plans that don't free enough yet and continue at the same candidate, by bucket of freed
+/// bytes. Each bucket keeps a few plans that differ in which shared data they partly deleted.
+type Waiting = BTreeMap<usize, Vec<Choice>>;
+
+struct Planner<'a> {
+    options: &'a CleanupOptions,
+    needed: usize,
+    /// Bytes per bucket of plans
+    granularity: usize,
+    exhaustive: bool,
+    candidates: Vec<Candidate>,
+    shared: Vec<Shared>,
+    /// The best plan that frees enough, adding to it can only make it worse
+    best: Option<Choice>,
+}
+
+impl Planner<'_> {
+    /// Keeps `choice` if it is the best plan so far, or one of the best to continue at
+    /// `position`
+    fn add(&mut self, waiting: &mut Waiting, position: usize, mut choice: Choice) {
+        let objective = self.options.objective;
+        if choice.freed >= self.needed {
+            if self
+                .best
+                .as_ref()
+                .is_none_or(|best| choice.compare(best, objective) == Ordering::Less)
+            {
+                self.best = Some(choice);
+            }
+            return;
+        }
+        // Data none of whose remaining links can be deleted anymore stays on the disk
+        choice
+            .pending
+            .retain(|&(data, _)| self.shared[data].last >= position);
+        let variants = waiting.entry(choice.freed / self.granularity).or_default();
+        if let Some(kept) = variants
+            .iter_mut()
+            .find(|kept| kept.pending == choice.pending)
+        {
+            if kept.freed != choice.freed {
+                self.exhaustive = false;
+            }
+            if choice.compare(kept, objective) == Ordering::Less {
+                *kept = choice;
+            }
+        } else if variants.len() < VARIANTS {
+            variants.push(choice);
+        } else {
+            self.exhaustive = false;
+            if let Some(worst) = variants
+                .iter_mut()
+                .max_by(|a, b| a.compare(b, objective))
+                .filter(|worst| choice.compare(worst, objective) == Ordering::Less)
+            {
+                *worst = choice;
+            }
+        }
+    }
+
+    /// `choice` followed by deleting the candidate at `position`
+    fn delete(&self, choice: &Choice, position: usize) -> Choice {
+        let candidate = &self.candidates[position];
+        let mut pending = choice.pending.clone();
+        for &(data, links) in &candidate.partly {
+            match pending.binary_search_by_key(&data, |&(pending, _)| pending) {
+                Ok(idx) => pending[idx].1 += links,
+                Err(idx) => pending.insert(idx, (data, links)),
+            }
+        }
+        // Shared data is freed once the deletions together take all of its links
+        let mut freed_together = 0;
+        pending.retain(|&(data, links)| {
+            let freed = links == self.shared[data].links;
+            if freed {
+                freed_together += self.shared[data].size;
+            }
+            !freed
+        });
+        Choice {
+            freed: choice.freed + candidate.freed + freed_together,
+            deletions: choice.deletions + 1,
+            pending,
+            last: Some(Rc::new(Deletion {
+                candidate: position,
+                previous: choice.last.clone(),
+            })),
+        }
+    }
+
+    /// Goes through the candidates, every plan either deleting one and skipping what is inside
+    /// it or moving on to the next. Returns the plans that got to the end without freeing enough.
+    fn search(&mut self) -> Waiting {
+        let count = self.candidates.len();
+        let mut waiting: Vec<Waiting> = (0..=count).map(|_| Waiting::new()).collect();
+        self.add(&mut waiting[0], 0, Choice::default());
+        for position in 0..count {
+            let choices = std::mem::take(&mut waiting[position]);
+            for choice in choices.into_values().flatten() {
+                if self.candidates[position].deletable {
+                    let end = self.candidates[position].end;
+                    let deleted = self.delete(&choice, position);
+                    self.add(&mut waiting[end], end, deleted);
+                }
+                self.add(&mut waiting[position + 1], position + 1, choice);
+            }
+        }
+        waiting.pop().unwrap_or_default()
+    }
+
+    /// The deletions making up `choice`, with what each of them frees on its own
+    fn deletions(&self, choice: &Choice) -> Vec<(FSPath, usize)> {
+        let mut deletions = vec![];
+        let mut last = choice.last.as_deref();
+        while let Some(deletion) = last {
+            let candidate = &self.candidates[deletion.candidate];
+            deletions.push((candidate.path.clone(), candidate.freed));
+            last = deletion.previous.as_deref();
+        }
+        deletions.sort();
+        deletions
+    }
+}
+
+/// The directories and hard links of `fs` in pre-order
+fn seen<F: FileSystem>(fs: &F) -> impl Iterator<Item = Seen> + '_ {
+    let mut stack = vec![(0, fs.top())];
+    // Paths of the directories containing the current node, by depth
+    let mut paths: Vec<FSPath> = vec![];
+    std::iter::from_fn(move || loop {
+        let (depth, node) = stack.pop()?;
+        stack.extend(fs.children_of(node).rev().map(|child| (depth + 1, child)));
+        match fs.kind_of(node) {
+            FSKind::Directory => {
+                paths.truncate(depth);
+                let path = match paths.last() {
+                    Some(parent) => parent.child(fs.name_of(node)),
+                    None => FSPath::root(),
+                };
+                paths.push(path.clone());
+                return Some(Seen::Directory {
+                    depth,
+                    path,
+                    size: fs.size_of(node),
+                });
+            }
+            FSKind::HardLink(size, id) => return Some(Seen::Link { depth, id, size }),
+            FSKind::File(_) | FSKind::Symlink(_) => {}
+        }
+    })
+}
+
+impl FSNode {
+    /// Finds directories to delete so that `options.required` bytes are free on a disk holding
+    /// `options.capacity`, treating this node as its root
+    pub fn plan_cleanup(&self, options: &CleanupOptions) -> Result<Plan, CleanupError> {
+        plan(self.size_below(), seen(&self), options)
+    }
+}
+
+impl FSArena {
+    /// Like [`FSNode::plan_cleanup`]
+    pub fn plan_cleanup(&self, options: &CleanupOptions) -> Result<Plan, CleanupError> {
+        plan(self.size_below(self.root()), seen(self), options)
+    }
+}
+
+fn plan(
+    used: usize,
+    nodes: impl IntoIterator<Item = Seen>,
+    options: &CleanupOptions,
+) -> Result<Plan, CleanupError> {
+    if used > options.capacity {
+        return Err(CleanupError::OverCapacity {
+            used,
+            capacity: options.capacity,
+        });
+    }
+    let needed = options.required.saturating_sub(options.capacity - used);
+    let mut planner = Planner {
+        options,
+        needed,
+        granularity: needed.div_ceil(BUCKETS).max(1),
+        exhaustive: true,
+        candidates: vec![],
+        shared: vec![],
+        best: None,
+    };
+    let best = if needed == 0 {
+        Choice::default()
+    } else {
+        (planner.candidates, planner.shared) = candidates(nodes, &options.protected);
+        let unfinished = planner.search();
+        match planner.best.take() {
+            Some(best) => best,
+            None => {
+                return Err(CleanupError::Impossible {
+                    needed,
+                    available: unfinished
+                        .values()
+                        .flatten()
+                        .map(|choice| choice.freed)
+                        .max()
+                        .unwrap_or(0),
+                })
+            }
+        }
+    };
+
+    Ok(Plan {
+        options: options.clone(),
+        used,
+        needed,
+        freed: best.freed,
+        deletions: planner.deletions(&best),
+        exhaustive: planner.exhaustive,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn directory(name: &str, size: usize) -> FSNode {
+        let mut directory = FSNode::new(String::from(name));
+        directory.add_child(FSNode::file(String::from("data"), size));
+        directory
+    }
+
+    /// `/` with `x/` (60, holding `x/w/` with 40), `y/` (50) and `z/` (120)
+    fn filesystem() -> FSNode {
+        let mut x = directory("x", 20);
+        x.add_child(directory("w", 40));
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(x);
+        root.add_child(directory("y", 50));
+        root.add_child(directory("z", 120));
+        root
+    }
+
+    fn options(required: usize, objective: Objective) -> CleanupOptions {
+        CleanupOptions {
+            capacity: 300,
+            required,
+            objective,
+            protected: vec![],
+        }
+    }
+
+    fn deleted(plan: &Plan) -> Vec<String> {
+        plan.deletions
+            .iter()
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_objectives() {
+        let root = filesystem();
+        let plan = root
+            .plan_cleanup(&options(180, Objective::FewestDeletions))
+            .unwrap();
+        assert_eq!(plan.needed, 110);
+        assert_eq!(deleted(&plan), vec!["/z"]);
+
+        let plan = root
+            .plan_cleanup(&options(180, Objective::LeastBytes))
+            .unwrap();
+        assert_eq!(deleted(&plan), vec!["/x", "/y"]);
+        assert_eq!(plan.freed, 110);
+        assert!(plan.exhaustive);
+
+        let plan = root
+            .plan_cleanup(&options(20, Objective::LeastBytes))
+            .unwrap();
+        assert_eq!((plan.needed, plan.deletions.len()), (0, 0));
+        assert!(plan
+            .explain()
+            .starts_with("230 of 300 bytes used, 20 must be free, so nothing"));
+    }
+
+    #[test]
+    fn test_protected() {
+        let root = filesystem();
+        let options = CleanupOptions {
+            protected: vec![FSPath::from("/z")],
+            ..options(180, Objective::FewestDeletions)
+        };
+        let plan = root.plan_cleanup(&options).unwrap();
+        assert_eq!(deleted(&plan), vec!["/x", "/y"]);
+
+        let options = CleanupOptions {
+            protected: vec![FSPath::from("/z"), FSPath::from("/x")],
+            ..options
+        };
+        assert_eq!(
+            root.plan_cleanup(&options),
+            Err(CleanupError::Impossible {
+                needed: 110,
+                available: 50
+            })
+        );
+    }
+
+    #[test]
+    fn test_hard_links() {
+        let mut a = directory("a", 10);
+        a.add_child(FSNode::hard_link(String::from("x"), 100, 1));
+        let mut b = directory("b", 10);
+        b.add_child(FSNode::hard_link(String::from("y"), 100, 1));
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(a);
+        root.add_child(b);
+        root.add_child(directory("c", 130));
+
+        // Deleting `/a` on its own leaves the data behind in `/b`
+        let plan = root
+            .plan_cleanup(&options(80, Objective::FewestDeletions))
+            .unwrap();
+        assert_eq!(plan.needed, 30);
+        assert_eq!(deleted(&plan), vec!["/c"]);
+
+        let plan = root
+            .plan_cleanup(&options(80, Objective::LeastBytes))
+            .unwrap();
+        assert_eq!(
+            plan.deletions,
+            vec![(FSPath::from("/a"), 10), (FSPath::from("/b"), 10)]
+        );
+        assert_eq!(plan.freed, 120);
+        assert!(plan.exhaustive);
+
+        // Only deleting both directories with a link frees enough
+        let options = CleanupOptions {
+            protected: vec![FSPath::from("/c")],
+            ..options(150, Objective::FewestDeletions)
+        };
+        let plan = root.plan_cleanup(&options).unwrap();
+        assert_eq!(plan.needed, 100);
+        assert_eq!(deleted(&plan), vec!["/a", "/b"]);
+        assert_eq!(plan.freed, 120);
+        assert_eq!(
+            root.plan_cleanup(&CleanupOptions {
+                required: 180,
+                ..options.clone()
+            }),
+            Err(CleanupError::Impossible {
+                needed: 130,
+                available: 120
+            })
+        );
+        // Plans for arenas are the same
+        assert_eq!(FSArena::from(root).plan_cleanup(&options), Ok(plan));
+    }
+
+    #[test]
+    fn test_explain() {
+        let plan = filesystem()
+            .plan_cleanup(&options(180, Objective::LeastBytes))
+            .unwrap();
+        assert_eq!(
+            plan.explain(),
+            "230 of 300 bytes used, 180 must be free, so at least 110 have to be deleted
+delete /x (60)
+delete /y (50)
+frees 110 in 2 deletion(s), leaving 180 free, chosen for least bytes deleted
+"
+        );
+        assert_eq!(
+            filesystem().plan_cleanup(&CleanupOptions {
+                capacity: 200,
+                ..options(0, Objective::LeastBytes)
+            }),
+            Err(CleanupError::OverCapacity {
+                used: 230,
+                capacity: 200
+            })
+        );
+    }
+}
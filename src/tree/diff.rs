@@ -0,0 +1,230 @@
+use super::{FSNode, FSPath};
+use std::collections::BTreeSet;
+
+/// How a single path differs between two trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Only in the new tree, with its total size
+    Added { size: usize, directory: bool },
+    /// Only in the old tree, with its total size
+    Removed { size: usize, directory: bool },
+    /// A file whose size changed
+    Resized { before: usize, after: usize },
+    /// A file that became a directory or the other way around
+    Replaced {
+        before: usize,
+        after: usize,
+        /// Whether it is a directory in the new tree
+        directory: bool,
+    },
+}
+
+/// A directory in both trees whose total size changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeDelta {
+    pub path: FSPath,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl SizeDelta {
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Diff {
+    /// Changes ordered by path. Contents of added or removed directories aren't listed
+    /// separately.
+    pub changes: Vec<(FSPath, Change)>,
+    /// Directories in both trees whose total changed, ordered by path
+    pub directories: Vec<SizeDelta>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// One line per change, followed by the totals of the directories that changed
+    pub fn render(&self) -> String {
+        let label = |path: &FSPath, directory: bool| match directory {
+            true if !path.is_root() => format!("{path}/"),
+            _ => path.to_string(),
+        };
+        let kind = |directory: bool| if directory { "directory" } else { "file" };
+
+        let mut output = String::new();
+        for (path, change) in &self.changes {
+            output.push_str(&match *change {
+                Change::Added { size, directory } => {
+                    format!("+ {} ({size})\n", label(path, directory))
+                }
+                Change::Removed { size, directory } => {
+                    format!("- {} ({size})\n", label(path, directory))
+                }
+                Change::Resized { before, after } => format!("~ {path} ({before} -> {after})\n"),
+                Change::Replaced {
+                    before,
+                    after,
+                    directory,
+                } => format!(
+                    "! {} ({} of {before} -> {} of {after})\n",
+                    label(path, directory),
+                    kind(!directory),
+                    kind(directory)
+                ),
+            });
+        }
+        if !self.directories.is_empty() {
+            output.push_str("directory totals:\n");
+        }
+        for delta in &self.directories {
+            output.push_str(&format!(
+                "{} {} -> {} ({:+})\n",
+                label(&delta.path, true),
+                delta.before,
+                delta.after,
+                delta.delta()
+            ));
+        }
+        output
+    }
+}
+
+impl FSNode {
+    /// Compares this tree with a newer version of it. The roots are matched up regardless of
+    /// their names.
+    pub fn diff(&self, new: &FSNode) -> Diff {
+        let mut diff = Diff::default();
+        let mut stack = vec![(FSPath::root(), self, new)];
+        while let Some((path, old, new)) = stack.pop() {
+            match (old.is_directory(), new.is_directory()) {
+                (true, true) => {
+                    if old.size_below() != new.size_below() {
+                        diff.directories.push(SizeDelta {
+                            path: path.clone(),
+                            before: old.size_below(),
+                            after: new.size_below(),
+                        });
+                    }
+                    let names: BTreeSet<&String> = old
+                        .children()
+                        .chain(new.children())
+                        .map(|(name, _)| name)
+                        .collect();
+                    for name in names {
+                        let child_path = path.child(name);
+                        match (old.child(name), new.child(name)) {
+                            (Some(old), Some(new)) => stack.push((child_path, old, new)),
+                            (Some(old), None) => diff.changes.push((
+                                child_path,
+                                Change::Removed {
+                                    size: old.size_below(),
+                                    directory: old.is_directory(),
+                                },
+                            )),
+                            (None, Some(new)) => diff.changes.push((
+                                child_path,
+                                Change::Added {
+                                    size: new.size_below(),
+                                    directory: new.is_directory(),
+                                },
+                            )),
+                            (None, None) => {}
+                        }
+                    }
+                }
+                (false, false) => {
+                    if old.size_below() != new.size_below() {
+                        diff.changes.push((
+                            path,
+                            Change::Resized {
+                                before: old.size_below(),
+                                after: new.size_below(),
+                            },
+                        ));
+                    }
+                }
+                (_, directory) => diff.changes.push((
+                    path,
+                    Change::Replaced {
+                        before: old.size_below(),
+                        after: new.size_below(),
+                        directory,
+                    },
+                )),
+            }
+        }
+        diff.changes.sort_by(|a, b| a.0.cmp(&b.0));
+        diff.directories.sort_by(|a, b| a.path.cmp(&b.path));
+        diff
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const OLD: &str = "dir /
+dir /a
+dir /a/e
+584 /a/e/i
+29116 /a/f
+14848514 /b.txt
+8504156 /c.dat
+dir /d
+4060174 /d/j
+";
+
+    const NEW: &str = "dir /
+dir /a
+584 /a/e
+30000 /a/f
+100 /a/g
+dir /b.txt
+1 /b.txt/x
+8504156 /c.dat
+";
+
+    #[test]
+    fn test_diff() {
+        let old = FSNode::from_lines(OLD).unwrap();
+        let new = FSNode::from_lines(NEW).unwrap();
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(old.diff(&old), Diff::default());
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.changes.len(), 5);
+        assert_eq!(
+            diff.directories,
+            vec![
+                SizeDelta {
+                    path: FSPath::root(),
+                    before: 27442544,
+                    after: 8534841
+                },
+                SizeDelta {
+                    path: FSPath::from("/a"),
+                    before: 29700,
+                    after: 30684
+                },
+            ]
+        );
+        assert_eq!(diff.directories[0].delta(), -18907703);
+        assert_eq!(
+            diff.render(),
+            "! /a/e (directory of 584 -> file of 584)
+~ /a/f (29116 -> 30000)
++ /a/g (100)
+! /b.txt/ (file of 14848514 -> directory of 1)
+- /d/ (4060174)
+directory totals:
+/ 27442544 -> 8534841 (-18907703)
+/a/ 29700 -> 30684 (+984)
+"
+        );
+    }
+}
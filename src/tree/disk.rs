@@ -0,0 +1,369 @@
+use super::path::glob_match;
+use super::{FSArena, FSNode, Metadata as NodeMetadata, NodeId};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ScanOptions {
+    /// Descend into symlinked directories and count symlinked files, instead of keeping the
+    /// symlinks themselves
+    pub follow_symlinks: bool,
+    /// Don't descend into directories on a different filesystem than the starting point
+    pub one_file_system: bool,
+    /// Glob patterns like `*.log` or `target`. Entries whose name matches are skipped, patterns
+    /// containing a `/` are matched against the path relative to the starting point instead.
+    pub ignore: Vec<String>,
+}
+
+impl ScanOptions {
+    fn ignores(&self, relative: &Path) -> bool {
+        let name = relative
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let relative = relative.to_string_lossy();
+        self.ignore.iter().any(|pattern| {
+            if pattern.contains('/') {
+                glob_match(pattern.trim_start_matches('/'), &relative)
+            } else {
+                glob_match(pattern, &name)
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "could not read `{}`: {}",
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+/// The tree found on disk
+#[derive(Debug)]
+pub struct DiskScan {
+    pub root: FSArena,
+    /// Entries that could not be read and are missing from the tree
+    pub skipped: Vec<ScanError>,
+}
+
+#[cfg(unix)]
+fn identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(unix)]
+fn links(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink()
+}
+
+#[cfg(not(unix))]
+fn links(_metadata: &Metadata) -> u64 {
+    1
+}
+
+#[cfg(unix)]
+fn node_metadata(metadata: &Metadata) -> NodeMetadata {
+    use std::os::unix::fs::MetadataExt;
+    NodeMetadata {
+        modified: metadata.modified().ok(),
+        permissions: Some(metadata.mode() & 0o7777),
+        owner: Some(metadata.uid()),
+    }
+}
+
+#[cfg(not(unix))]
+fn node_metadata(metadata: &Metadata) -> NodeMetadata {
+    NodeMetadata {
+        modified: metadata.modified().ok(),
+        ..NodeMetadata::default()
+    }
+}
+
+struct Scanner<'a> {
+    options: &'a ScanOptions,
+    start: &'a Path,
+    device: Option<u64>,
+    /// Directories on the current path, so following symlinks can't loop forever
+    ancestors: HashSet<(u64, u64)>,
+    /// Ids handed out to files with several hard links, by their device and inode
+    hard_links: HashMap<(u64, u64), u64>,
+    skipped: Vec<ScanError>,
+    tree: FSArena,
+}
+
+impl Scanner<'_> {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if self.options.follow_symlinks {
+            fs::metadata(path)
+        } else {
+            fs::symlink_metadata(path)
+        }
+    }
+
+    /// Adds the node for `path` to the directory `parent`, unless it is skipped
+    fn scan(&mut self, parent: NodeId, path: &Path, name: String) {
+        let metadata = match self.metadata(path) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                self.skipped.push(ScanError {
+                    path: path.to_path_buf(),
+                    error,
+                });
+                return;
+            }
+        };
+        let size = metadata.len() as usize;
+        let node = if metadata.is_file() {
+            match identity(&metadata) {
+                Some(id) if links(&metadata) > 1 => {
+                    let next = self.hard_links.len() as u64;
+                    let id = *self.hard_links.entry(id).or_insert(next);
+                    FSNode::hard_link(name, size, id)
+                }
+                _ => FSNode::file(name, size),
+            }
+        } else if metadata.is_symlink() {
+            match fs::read_link(path) {
+                Ok(target) => FSNode::symlink(name, target.to_string_lossy().into_owned()),
+                Err(error) => {
+                    self.skipped.push(ScanError {
+                        path: path.to_path_buf(),
+                        error,
+                    });
+                    return;
+                }
+            }
+        } else if metadata.is_dir() && self.enters(&metadata) {
+            FSNode::new(name)
+        } else {
+            // Sockets, devices and directories that aren't entered
+            return;
+        };
+        let node = node.with_metadata(node_metadata(&metadata));
+        let id = self
+            .tree
+            .add_child(parent, node)
+            .expect("only directories are scanned into");
+        if metadata.is_dir() {
+            self.directory(path, id, &metadata);
+        }
+    }
+
+    fn enters(&self, metadata: &Metadata) -> bool {
+        let id = identity(metadata);
+        if self.options.one_file_system && id.map(|(dev, _)| dev) != self.device {
+            return false;
+        }
+        id.is_none_or(|id| !self.ancestors.contains(&id))
+    }
+
+    /// Scans the entries of the directory at `path` into the node `directory`
+    fn directory(&mut self, path: &Path, directory: NodeId, metadata: &Metadata) {
+        let id = identity(metadata);
+        if let Some(id) = id {
+            self.ancestors.insert(id);
+        }
+
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                let mut found = vec![];
+                for entry in entries {
+                    match entry {
+                        Ok(entry) => found.push(entry),
+                        Err(error) => self.skipped.push(ScanError {
+                            path: path.to_path_buf(),
+                            error,
+                        }),
+                    }
+                }
+                // `read_dir` returns entries in whatever order the filesystem keeps them in
+                found.sort_by_key(|entry| entry.file_name());
+                for entry in found {
+                    let child_path = entry.path();
+                    let relative = child_path.strip_prefix(self.start).unwrap_or(&child_path);
+                    if self.options.ignores(relative) {
+                        continue;
+                    }
+                    let child_name = entry.file_name().to_string_lossy().into_owned();
+                    self.scan(directory, &child_path, child_name);
+                }
+            }
+            Err(error) => self.skipped.push(ScanError {
+                path: path.to_path_buf(),
+                error,
+            }),
+        }
+
+        if let Some(id) = id {
+            self.ancestors.remove(&id);
+        }
+    }
+}
+
+/// Reads the directory at `path` into an [`FSArena`], whose root is named after `path`.
+/// Files are counted with their length, so sizes match `du --apparent-size`.
+pub fn scan(path: &Path, options: &ScanOptions) -> Result<DiskScan, ScanError> {
+    // The starting point itself is always followed, even if it is a symlink
+    let metadata = fs::metadata(path).map_err(|error| ScanError {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    if !metadata.is_dir() {
+        return Err(ScanError {
+            path: path.to_path_buf(),
+            error: io::Error::new(io::ErrorKind::InvalidInput, "not a directory"),
+        });
+    }
+
+    let root = FSNode::new(path.display().to_string()).with_metadata(node_metadata(&metadata));
+    let mut scanner = Scanner {
+        options,
+        start: path,
+        device: identity(&metadata).map(|(dev, _)| dev),
+        ancestors: HashSet::new(),
+        hard_links: HashMap::new(),
+        skipped: vec![],
+        tree: FSArena::from(root),
+    };
+    let root = scanner.tree.root();
+    scanner.directory(path, root, &metadata);
+    Ok(DiskScan {
+        root: scanner.tree,
+        skipped: scanner.skipped,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{ChildOrder, FSEntry, FSKind, FSPath};
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-disk-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/e")).unwrap();
+        fs::write(dir.join("b.txt"), [0; 100]).unwrap();
+        fs::write(dir.join("a/f"), [0; 20]).unwrap();
+        fs::write(dir.join("a/e/i.log"), [0; 3]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_scan() {
+        let dir = temp_dir("scan");
+        let scan = scan(&dir, &ScanOptions::default()).unwrap();
+        assert!(scan.skipped.is_empty());
+        let arena = scan.root;
+        assert_eq!(arena.node_count(), 6);
+        assert_eq!(arena.size_below(arena.root()), 123);
+        let e = arena.get(&FSPath::from("/a/e")).unwrap();
+        assert_eq!(arena.size_below(e), 3);
+        assert_eq!(arena.size_below(arena.parent(e).unwrap()), 23);
+        let mut root = FSNode::from(arena);
+        assert_eq!(root.get(&FSPath::from("/a")).unwrap().size_below(), 23);
+        root.set_child_order(ChildOrder::Name);
+        let root = FSNode::from(FSArena::from(root));
+        let names: Vec<&String> = root.children().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["a", "b.txt"]);
+
+        let options = ScanOptions {
+            ignore: vec![String::from("*.log"), String::from("/b.txt")],
+            ..ScanOptions::default()
+        };
+        let arena = super::scan(&dir, &options).unwrap().root;
+        assert_eq!(arena.size_below(arena.root()), 20);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks() {
+        let dir = temp_dir("symlinks");
+        std::os::unix::fs::symlink(dir.join("a"), dir.join("a/e/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("b.txt"), dir.join("link.txt")).unwrap();
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..ScanOptions::default()
+        };
+        // The loop back to `a` is cut off, the linked file is counted twice
+        let arena = scan(&dir, &options).unwrap().root;
+        assert_eq!(arena.size_below(arena.root()), 223);
+
+        let root = FSNode::from(scan(&dir, &ScanOptions::default()).unwrap().root);
+        assert_eq!(root.size_below(), 123);
+        let link = root.get(&FSPath::from("/link.txt")).unwrap();
+        assert_eq!(
+            link.entry(),
+            &FSEntry::Symlink(
+                String::from("link.txt"),
+                dir.join("b.txt").display().to_string()
+            )
+        );
+        assert!(link.metadata().is_some_and(|m| m.owner.is_some()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hard_links() {
+        let dir = temp_dir("hard-links");
+        fs::hard_link(dir.join("b.txt"), dir.join("a/e/b.txt")).unwrap();
+        let arena = scan(&dir, &ScanOptions::default()).unwrap().root;
+        let links: Vec<NodeId> = ["/b.txt", "/a/e/b.txt"]
+            .iter()
+            .map(|path| arena.get(&FSPath::from(*path)).unwrap())
+            .collect();
+        // Both links keep the real size and share an id
+        let entries: Vec<(usize, u64)> = links
+            .iter()
+            .map(|&link| match arena.kind(link) {
+                FSKind::HardLink(size, id) => (*size, *id),
+                kind => panic!("{kind:?} is not a hard link"),
+            })
+            .collect();
+        assert_eq!(entries[0].0, 100);
+        assert_eq!(entries[0], entries[1]);
+        // Every directory counts the data once, however many links to it it holds
+        let sizes: Vec<(String, usize)> = ["/", "/a", "/a/e"]
+            .iter()
+            .map(|path| {
+                let id = arena.get(&FSPath::from(*path)).unwrap();
+                (path.to_string(), arena.size_below(id))
+            })
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                (String::from("/"), 123),
+                (String::from("/a"), 123),
+                (String::from("/a/e"), 103)
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,734 @@
+use super::{Arena, FSPath, Measure, NavigationError, NodeId, Tree, Zipper};
+use std::collections::{HashMap, VecDeque};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FSEntry {
+    Directory(String),
+    File(String, usize),
+    /// A symbolic link to the given target, which takes up no space itself
+    Symlink(String, String),
+    /// One of several names for the same data, which is identified by the last field. Sizes
+    /// count that data once, no matter how many links to it a directory contains.
+    HardLink(String, usize, u64),
+}
+
+impl FSEntry {
+    pub fn name(&self) -> &str {
+        match self {
+            FSEntry::Directory(name) => name,
+            FSEntry::File(name, _) => name,
+            FSEntry::Symlink(name, _) => name,
+            FSEntry::HardLink(name, ..) => name,
+        }
+    }
+
+    /// Everything but the name
+    pub fn kind(&self) -> FSKind<&str> {
+        match self {
+            FSEntry::Directory(_) => FSKind::Directory,
+            FSEntry::File(_, size) => FSKind::File(*size),
+            FSEntry::Symlink(_, target) => FSKind::Symlink(target),
+            FSEntry::HardLink(_, size, id) => FSKind::HardLink(*size, *id),
+        }
+    }
+
+    pub fn into_parts(self) -> (String, FSKind) {
+        match self {
+            FSEntry::Directory(name) => (name, FSKind::Directory),
+            FSEntry::File(name, size) => (name, FSKind::File(size)),
+            FSEntry::Symlink(name, target) => (name, FSKind::Symlink(target)),
+            FSEntry::HardLink(name, size, id) => (name, FSKind::HardLink(size, id)),
+        }
+    }
+}
+
+/// An [`FSEntry`] without its name, with the symlink target owned or borrowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FSKind<S = String> {
+    Directory,
+    File(usize),
+    Symlink(S),
+    HardLink(usize, u64),
+}
+
+impl FSKind {
+    pub fn as_deref(&self) -> FSKind<&str> {
+        match self {
+            FSKind::Directory => FSKind::Directory,
+            FSKind::File(size) => FSKind::File(*size),
+            FSKind::Symlink(target) => FSKind::Symlink(target),
+            FSKind::HardLink(size, id) => FSKind::HardLink(*size, *id),
+        }
+    }
+
+    pub fn with_name(self, name: String) -> FSEntry {
+        match self {
+            FSKind::Directory => FSEntry::Directory(name),
+            FSKind::File(size) => FSEntry::File(name, size),
+            FSKind::Symlink(target) => FSEntry::Symlink(name, target),
+            FSKind::HardLink(size, id) => FSEntry::HardLink(name, size, id),
+        }
+    }
+}
+
+/// Details about a node that a day 7 transcript doesn't have, but a real filesystem does
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Metadata {
+    pub modified: Option<SystemTime>,
+    /// Unix permission bits like `0o644`
+    pub permissions: Option<u32>,
+    /// User id of the owner
+    pub owner: Option<u32>,
+}
+
+/// What is stored in every node of an [`FSNode`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FSItem {
+    pub entry: FSEntry,
+    /// Boxed since trees built from transcripts have none
+    pub metadata: Option<Box<Metadata>>,
+}
+
+/// What an [`FSArena`] stores for every node: an [`FSItem`] without the name, since the arena
+/// keeps each distinct name once for all nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FSData {
+    pub kind: FSKind,
+    pub metadata: Option<Box<Metadata>>,
+}
+
+impl FSItem {
+    fn into_parts(self) -> (String, FSData) {
+        let (name, kind) = self.entry.into_parts();
+        let metadata = self.metadata;
+        (name, FSData { kind, metadata })
+    }
+}
+
+/// Space taken up by a subtree
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Usage {
+    /// Total size, counting the data of hard links once
+    pub bytes: usize,
+    /// Hard-linked data below the node, with its size and the number of links to it
+    shared: HashMap<u64, (usize, usize)>,
+}
+
+/// Caches the total size of every directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size;
+
+impl Usage {
+    fn of(kind: FSKind<&str>) -> Usage {
+        match kind {
+            FSKind::Directory | FSKind::Symlink(_) => Usage::default(),
+            FSKind::File(size) => Usage {
+                bytes: size,
+                shared: HashMap::new(),
+            },
+            FSKind::HardLink(size, id) => Usage {
+                bytes: size,
+                shared: HashMap::from([(id, (size, 1))]),
+            },
+        }
+    }
+
+    fn add(&mut self, child: &Usage) {
+        self.bytes += child.bytes;
+        for (&id, &(size, links)) in &child.shared {
+            let shared = self.shared.entry(id).or_insert((size, 0));
+            if shared.1 > 0 {
+                // Already counted
+                self.bytes -= size;
+            }
+            shared.1 += links;
+        }
+    }
+
+    fn remove(&mut self, child: &Usage) {
+        self.bytes -= child.bytes;
+        for (id, &(size, links)) in &child.shared {
+            if let Some(shared) = self.shared.get_mut(id) {
+                shared.1 -= links;
+                if shared.1 == 0 {
+                    self.shared.remove(id);
+                } else {
+                    // Still there through other links
+                    self.bytes += size;
+                }
+            }
+        }
+    }
+}
+
+impl Measure<FSItem> for Size {
+    type Total = Usage;
+
+    fn of(item: &FSItem) -> Usage {
+        Usage::of(item.entry.kind())
+    }
+    fn add(total: &mut Usage, child: &Usage) {
+        total.add(child);
+    }
+    fn remove(total: &mut Usage, child: &Usage) {
+        total.remove(child);
+    }
+}
+
+impl Measure<FSData> for Size {
+    type Total = Usage;
+
+    fn of(data: &FSData) -> Usage {
+        Usage::of(data.kind.as_deref())
+    }
+    fn add(total: &mut Usage, child: &Usage) {
+        total.add(child);
+    }
+    fn remove(total: &mut Usage, child: &Usage) {
+        total.remove(child);
+    }
+}
+
+/// A filesystem tree, with children keyed by their name
+pub type FSNode = Tree<String, FSItem, Size>;
+pub type FSNodeZipper = Zipper<String, FSItem, Size>;
+/// A filesystem tree stored in an [`Arena`], for trees with millions of nodes. Names are only
+/// kept by the arena, so nodes are converted from and to [`FSNode`] with `From`.
+pub type FSArena = Arena<String, FSData, Size>;
+
+/// Read access to a filesystem tree, so rendering, queries and cleanup plans work on an
+/// [`FSNode`] as well as on an [`FSArena`] without turning one into the other
+pub trait FileSystem {
+    /// Refers to a node of the tree
+    type Node: Copy;
+
+    fn top(&self) -> Self::Node;
+    fn name_of(&self, node: Self::Node) -> &str;
+    fn kind_of(&self, node: Self::Node) -> FSKind<&str>;
+    /// Total size of the node, which is cached for directories
+    fn size_of(&self, node: Self::Node) -> usize;
+    fn children_of(&self, node: Self::Node) -> impl DoubleEndedIterator<Item = Self::Node>;
+
+    /// Every node with its path relative to the top, in breadth-first order
+    fn paths_of(&self) -> Vec<(FSPath, Self::Node)> {
+        let mut result = vec![];
+        let mut queue = VecDeque::from([(FSPath::root(), self.top())]);
+        while let Some((path, node)) = queue.pop_front() {
+            for child in self.children_of(node) {
+                queue.push_back((path.child(self.name_of(child)), child))
+            }
+            result.push((path, node));
+        }
+        result
+    }
+}
+
+impl<'a> FileSystem for &'a FSNode {
+    type Node = &'a FSNode;
+
+    fn top(&self) -> &'a FSNode {
+        self
+    }
+    fn name_of(&self, node: &'a FSNode) -> &str {
+        node.entry().name()
+    }
+    fn kind_of(&self, node: &'a FSNode) -> FSKind<&str> {
+        node.entry().kind()
+    }
+    fn size_of(&self, node: &'a FSNode) -> usize {
+        node.size_below()
+    }
+    fn children_of(&self, node: &'a FSNode) -> impl DoubleEndedIterator<Item = &'a FSNode> {
+        node.children().map(|(_, child)| child)
+    }
+}
+
+impl FileSystem for FSArena {
+    type Node = NodeId;
+
+    fn top(&self) -> NodeId {
+        self.root()
+    }
+    fn name_of(&self, id: NodeId) -> &str {
+        self.name(id)
+    }
+    fn kind_of(&self, id: NodeId) -> FSKind<&str> {
+        self.kind(id).as_deref()
+    }
+    fn size_of(&self, id: NodeId) -> usize {
+        self.size_below(id)
+    }
+    fn children_of(&self, id: NodeId) -> impl DoubleEndedIterator<Item = NodeId> {
+        self.children(id).map(|(_, child)| child)
+    }
+}
+
+impl FSNode {
+    pub fn from_entry(entry: FSEntry) -> FSNode {
+        Tree::leaf(FSItem {
+            entry,
+            metadata: None,
+        })
+    }
+
+    /// An empty directory
+    pub fn new(name: String) -> FSNode {
+        FSNode::from_entry(FSEntry::Directory(name))
+    }
+
+    pub fn file(name: String, size: usize) -> FSNode {
+        FSNode::from_entry(FSEntry::File(name, size))
+    }
+
+    pub fn symlink(name: String, target: String) -> FSNode {
+        FSNode::from_entry(FSEntry::Symlink(name, target))
+    }
+
+    /// A file sharing its data with every other hard link with the same `id`
+    pub fn hard_link(name: String, size: usize, id: u64) -> FSNode {
+        FSNode::from_entry(FSEntry::HardLink(name, size, id))
+    }
+
+    pub fn with_metadata(mut self, metadata: Metadata) -> FSNode {
+        self.update_value(|item| item.metadata = Some(Box::new(metadata)));
+        self
+    }
+
+    pub fn entry(&self) -> &FSEntry {
+        &self.value().entry
+    }
+
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.value().metadata.as_deref()
+    }
+
+    /// The part of the name after the last `.`, unless that is the first character
+    pub fn extension(&self) -> Option<&str> {
+        match self.entry().name().rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => Some(extension),
+            _ => None,
+        }
+    }
+
+    /// Creates a directory from existing children, which are inserted in the order of their names
+    pub fn directory(name: String, children: HashMap<String, FSNode>) -> FSNode {
+        let mut children: Vec<(String, FSNode)> = children.into_iter().collect();
+        children.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut directory = FSNode::new(name);
+        for (name, child) in children {
+            directory.insert_child(name, child);
+        }
+        directory
+    }
+
+    pub fn name(&self) -> String {
+        String::from(self.entry().name())
+    }
+
+    /// Adds `child` under its name. Files can't have children, so this does nothing for them.
+    pub fn add_child(&mut self, child: FSNode) {
+        if self.is_directory() {
+            self.insert_child(child.name(), child);
+        }
+    }
+
+    /// Total size of the node, which is cached for directories
+    pub fn size_below(&self) -> usize {
+        self.total().bytes
+    }
+
+    /// Every directory at or below this node together with its total size, parents first
+    pub fn directory_sizes(&self) -> Vec<(&FSNode, usize)> {
+        self.fold(|node, children: Vec<Vec<(&FSNode, usize)>>| {
+            if !node.is_directory() {
+                return vec![];
+            }
+            let mut sizes = vec![(node, node.size_below())];
+            sizes.extend(children.into_iter().flatten());
+            sizes
+        })
+    }
+
+    pub fn visit_nodes<'a, F>(&'a self, mut callback: F)
+    where
+        F: FnMut(&'a FSNode),
+    {
+        for (_, node) in self.iter_bfs() {
+            callback(node);
+        }
+    }
+
+    /// Every node at or below this one, paired with its path relative to this node's root,
+    /// in breadth-first order
+    pub fn paths(&self) -> Vec<(FSPath, &FSNode)> {
+        let mut result = vec![];
+        let mut queue = VecDeque::from([(FSPath::root(), self)]);
+        while let Some((path, node)) = queue.pop_front() {
+            for (name, child) in node.children() {
+                queue.push_back((path.child(name), child))
+            }
+            result.push((path, node));
+        }
+        result
+    }
+
+    /// Looks up a node, treating this node as the root for both absolute and relative paths
+    pub fn get(&self, path: &FSPath) -> Option<&FSNode> {
+        self.get_at(path.components())
+    }
+
+    /// Like [`FSNode::get`], but mutable. Changing a size through the returned reference does not
+    /// update the cached sizes of its ancestors, use [`FSNode::update`] for that.
+    pub fn get_mut(&mut self, path: &FSPath) -> Option<&mut FSNode> {
+        self.get_mut_at(path.components())
+    }
+
+    /// Applies `f` to the node at `path` and refreshes the cached sizes above it
+    pub fn update<R>(&mut self, path: &FSPath, f: impl FnOnce(&mut FSNode) -> R) -> Option<R> {
+        self.update_at(path.components(), f)
+    }
+
+    /// The path of `node`, which must be a reference into this tree
+    pub fn path_of(&self, node: &FSNode) -> Option<FSPath> {
+        self.paths()
+            .into_iter()
+            .find(|(_, candidate)| std::ptr::eq(*candidate, node))
+            .map(|(path, _)| path)
+    }
+
+    pub fn is_directory(&self) -> bool {
+        matches!(self.entry(), FSEntry::Directory(_))
+    }
+}
+
+impl FSNodeZipper {
+    /// Adds `child` under its name to the focused directory
+    pub fn add_child(&mut self, child: FSNode) -> &mut Self {
+        if self.node().is_directory() {
+            self.insert_child(child.name(), child);
+        }
+        self
+    }
+
+    pub fn current_path(&self) -> FSPath {
+        let mut path = FSPath::root();
+        for name in self.keys() {
+            path.push(name);
+        }
+        path
+    }
+
+    /// Moves along `path`, which may be absolute or relative and contain `..`.
+    /// If any step fails, the focus stays where it was.
+    pub fn cd(&mut self, path: &FSPath) -> Result<&mut Self, NavigationError<String>> {
+        let start = self.current_path();
+        if let Err(err) = self.follow(path) {
+            self.root();
+            for name in start.components() {
+                self.child(name)
+                    .expect("the starting point is still in the tree");
+            }
+            return Err(err);
+        }
+        Ok(self)
+    }
+
+    fn follow(&mut self, path: &FSPath) -> Result<(), NavigationError<String>> {
+        if path.is_absolute() {
+            self.root();
+        }
+        for name in path.components() {
+            if name == ".." {
+                self.parent()?;
+                continue;
+            }
+            match self.node().child(name) {
+                Some(child) if !child.is_directory() => {
+                    return Err(NavigationError::NotADirectory(name.clone()))
+                }
+                _ => self.child(name)?,
+            };
+        }
+        Ok(())
+    }
+}
+
+impl From<FSNode> for FSArena {
+    fn from(root: FSNode) -> FSArena {
+        let name = root.name();
+        FSArena::from_tree_with(root, Some(name), |item| item.into_parts().1)
+    }
+}
+
+impl From<FSArena> for FSNode {
+    fn from(arena: FSArena) -> FSNode {
+        arena.into_tree_with(|name, data| FSItem {
+            entry: data.kind.with_name(name.cloned().unwrap_or_default()),
+            metadata: data.metadata,
+        })
+    }
+}
+
+impl FSArena {
+    pub fn name(&self, id: NodeId) -> &str {
+        self.key(id).map_or("", String::as_str)
+    }
+
+    pub fn kind(&self, id: NodeId) -> &FSKind {
+        &self.value(id).kind
+    }
+
+    pub fn metadata(&self, id: NodeId) -> Option<&Metadata> {
+        self.value(id).metadata.as_deref()
+    }
+
+    pub fn size_below(&self, id: NodeId) -> usize {
+        self.total(id).bytes
+    }
+
+    pub fn is_directory(&self, id: NodeId) -> bool {
+        matches!(self.kind(id), FSKind::Directory)
+    }
+
+    /// Adds `child` under its name to the directory `id`, returning where it ended up. Files
+    /// can't have children, so this does nothing for them.
+    pub fn add_child(&mut self, id: NodeId, child: FSNode) -> Option<NodeId> {
+        if !self.is_directory(id) {
+            return None;
+        }
+        Some(self.insert_child_with(id, child.name(), child, |item| item.into_parts().1))
+    }
+
+    /// Looks up a node, treating both absolute and relative paths as starting at the root
+    pub fn get(&self, path: &FSPath) -> Option<NodeId> {
+        self.get_at(self.root(), path.components())
+    }
+
+    pub fn path_of(&self, id: NodeId) -> FSPath {
+        let mut path = FSPath::root();
+        for name in self.keys_of(id) {
+            path.push(name);
+        }
+        path
+    }
+
+    /// Where [`FSNodeZipper::cd`] would move to from `from`
+    pub fn cd(&self, from: NodeId, path: &FSPath) -> Result<NodeId, NavigationError<String>> {
+        let start = if path.is_absolute() {
+            self.root()
+        } else {
+            from
+        };
+        path.components().iter().try_fold(start, |node, name| {
+            if name == ".." {
+                return self.parent(node).ok_or(NavigationError::AboveRoot);
+            }
+            match self.child(node, name) {
+                Some(child) if self.is_directory(child) => Ok(child),
+                Some(_) => Err(NavigationError::NotADirectory(name.clone())),
+                None => Err(NavigationError::NoSuchChild(name.clone())),
+            }
+        })
+    }
+
+    /// Every directory together with its total size, parents before their children
+    pub fn directory_sizes(&self) -> Vec<(NodeId, usize)> {
+        self.iter_preorder(self.root())
+            .filter(|&id| self.is_directory(id))
+            .map(|id| (id, self.size_below(id)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn file(name: &str, size: usize) -> FSNode {
+        FSNode::file(String::from(name), size)
+    }
+
+    #[test]
+    fn test_sizes_follow_zipper_edits() {
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(file("a", 10));
+        root.add_child(FSNode::new(String::from("d")));
+        assert_eq!(root.size_below(), 10);
+
+        let mut zipper = root.zipper();
+        zipper
+            .child(&String::from("d"))
+            .unwrap()
+            .add_child(file("b", 5))
+            .add_child(file("c", 7));
+        let root = zipper.finish();
+        assert_eq!(root.size_below(), 22);
+
+        let mut sizes: Vec<(String, usize)> = root
+            .directory_sizes()
+            .into_iter()
+            .map(|(node, size)| (node.name(), size))
+            .collect();
+        sizes.sort();
+        assert_eq!(
+            sizes,
+            vec![(String::from("/"), 22), (String::from("d"), 12)]
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        let mut root = FSNode::new(String::from("/"));
+        let mut a = FSNode::new(String::from("a"));
+        a.add_child(file("e", 3));
+        root.add_child(a);
+        root.add_child(file("e", 4));
+
+        let e = root.get(&FSPath::from("/a/e")).unwrap();
+        assert_eq!(e.size_below(), 3);
+        assert_eq!(root.path_of(e), Some(FSPath::from("/a/e")));
+        assert_eq!(root.get(&FSPath::from("a/x")), None);
+        assert_eq!(root.get(&FSPath::from("/")), Some(&root));
+
+        let mut paths: Vec<String> = root
+            .paths()
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/", "/a", "/a/e", "/e"]);
+    }
+
+    #[test]
+    fn test_cd() {
+        let mut root = FSNode::new(String::from("/"));
+        let mut a = FSNode::new(String::from("a"));
+        a.add_child(FSNode::new(String::from("e")));
+        root.add_child(a);
+        root.add_child(file("f", 1));
+
+        let mut zipper = root.zipper();
+        zipper.cd(&FSPath::from("a/e")).unwrap();
+        assert_eq!(zipper.current_path(), FSPath::from("/a/e"));
+        zipper.cd(&FSPath::from("../..")).unwrap();
+        assert!(zipper.is_root());
+
+        zipper.cd(&FSPath::from("/a")).unwrap();
+        assert_eq!(
+            zipper.cd(&FSPath::from("e/x")).unwrap_err(),
+            NavigationError::NoSuchChild(String::from("x"))
+        );
+        assert_eq!(
+            zipper.cd(&FSPath::from("/f")).unwrap_err(),
+            NavigationError::NotADirectory(String::from("f"))
+        );
+        assert_eq!(
+            zipper.cd(&FSPath::from("../..")).unwrap_err(),
+            NavigationError::AboveRoot
+        );
+        assert_eq!(zipper.current_path(), FSPath::from("/a"));
+    }
+
+    #[test]
+    fn test_update_refreshes_sizes() {
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(FSNode::new(String::from("a")));
+        root.update(&FSPath::from("/a"), |a| a.add_child(file("f", 9)));
+        assert_eq!(root.size_below(), 9);
+        assert_eq!(root.update(&FSPath::from("/b"), |_| ()), None);
+    }
+
+    #[test]
+    fn test_hard_links() {
+        let link = |name: &str| FSNode::hard_link(String::from(name), 100, 7);
+        let mut root = FSNode::new(String::from("/"));
+        let mut a = FSNode::new(String::from("a"));
+        a.add_child(link("x"));
+        a.add_child(link("y"));
+        a.add_child(file("f", 5));
+        assert_eq!(a.size_below(), 105);
+        root.add_child(a);
+        root.add_child(link("z"));
+        root.add_child(FSNode::symlink(String::from("s"), String::from("a/f")));
+        assert_eq!(root.size_below(), 105);
+
+        root.update(&FSPath::from("/a"), |a| a.remove_child(&String::from("x")));
+        assert_eq!(root.get(&FSPath::from("/a")).unwrap().size_below(), 105);
+        root.remove_child(&String::from("a"));
+        assert_eq!(root.size_below(), 100);
+        root.remove_child(&String::from("z"));
+        assert_eq!(root.size_below(), 0);
+
+        let mut zipper = root.zipper();
+        zipper.add_child(link("x")).add_child(link("y"));
+        assert_eq!(zipper.finish().size_below(), 100);
+    }
+
+    #[test]
+    fn test_metadata() {
+        assert_eq!(file("a.tar.gz", 1).extension(), Some("gz"));
+        assert_eq!(file(".profile", 1).extension(), None);
+        assert_eq!(file("Makefile", 1).metadata(), None);
+
+        let metadata = Metadata {
+            permissions: Some(0o644),
+            owner: Some(1000),
+            ..Metadata::default()
+        };
+        let node = file("a", 3).with_metadata(metadata.clone());
+        assert_eq!(node.metadata(), Some(&metadata));
+        assert_eq!(node.size_below(), 3);
+    }
+
+    #[test]
+    fn test_arena() {
+        let mut root = FSNode::new(String::from("/"));
+        let mut a = FSNode::new(String::from("a"));
+        a.add_child(FSNode::new(String::from("e")));
+        a.add_child(FSNode::hard_link(String::from("x"), 10, 1));
+        root.add_child(a);
+        root.add_child(FSNode::hard_link(String::from("y"), 10, 1));
+
+        let mut arena = FSArena::from(root);
+        let e = arena.get(&FSPath::from("/a/e")).unwrap();
+        assert_eq!(arena.path_of(e), FSPath::from("/a/e"));
+        assert_eq!(arena.cd(e, &FSPath::from("../..")), Ok(arena.root()));
+        assert_eq!(
+            arena.cd(e, &FSPath::from("/y")),
+            Err(NavigationError::NotADirectory(String::from("y")))
+        );
+
+        let f = arena.add_child(e, file("f", 5)).unwrap();
+        assert_eq!(arena.add_child(f, file("g", 1)), None);
+        let sizes: Vec<(String, usize)> = arena
+            .directory_sizes()
+            .into_iter()
+            .map(|(id, size)| (arena.path_of(id).to_string(), size))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                (String::from("/"), 15),
+                (String::from("/a"), 15),
+                (String::from("/a/e"), 5)
+            ]
+        );
+        assert_eq!(arena.name(f), "f");
+        assert_eq!(arena.kind(f), &FSKind::File(5));
+        arena.remove_child(arena.root(), &String::from("y"));
+        let root = FSNode::from(arena);
+        assert_eq!(root.size_below(), 15);
+        assert_eq!(root.name(), "/");
+        assert_eq!(root.get(&FSPath::from("/a/e/f")), Some(&file("f", 5)));
+    }
+
+    #[test]
+    fn test_replacing_child_updates_size() {
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(file("a", 10));
+        root.add_child(file("a", 3));
+        assert_eq!(root.size_below(), 3);
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A normalized path to a node, e.g. `/a/e` or `../d`.
+/// Absolute paths start at the root of the tree, relative ones at the node they are resolved from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FSPath {
+    absolute: bool,
+    components: Vec<String>,
+}
+
+impl FSPath {
+    pub fn root() -> FSPath {
+        FSPath {
+            absolute: true,
+            components: vec![],
+        }
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.absolute
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.absolute && self.components.is_empty()
+    }
+
+    /// Names along the path. Relative paths may start with `..` components.
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        match self.components.last() {
+            Some(name) if name != ".." => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Appends a single component, resolving `.` and `..`
+    pub fn push(&mut self, name: &str) {
+        match name {
+            "" | "." => {}
+            ".." => match self.components.last() {
+                Some(last) if last != ".." => {
+                    self.components.pop();
+                }
+                // `..` above the root is the root itself
+                _ if self.absolute => {}
+                _ => self.components.push(String::from("..")),
+            },
+            name => self.components.push(String::from(name)),
+        }
+    }
+
+    /// The path with its last component removed, or `None` for the root and empty paths
+    pub fn parent(&self) -> Option<FSPath> {
+        self.file_name()?;
+        let mut parent = self.clone();
+        parent.components.pop();
+        Some(parent)
+    }
+
+    /// `other` resolved against `self`, which is `other` itself if it is absolute
+    pub fn join(&self, other: &FSPath) -> FSPath {
+        if other.absolute {
+            return other.clone();
+        }
+        let mut result = self.clone();
+        for name in &other.components {
+            result.push(name);
+        }
+        result
+    }
+
+    /// Whether `other` is this path or one of its ancestors
+    pub fn starts_with(&self, other: &FSPath) -> bool {
+        self.absolute == other.absolute && self.components.starts_with(&other.components)
+    }
+
+    pub fn child(&self, name: &str) -> FSPath {
+        let mut result = self.clone();
+        result.push(name);
+        result
+    }
+}
+
+impl From<&str> for FSPath {
+    fn from(path: &str) -> FSPath {
+        let mut result = FSPath {
+            absolute: path.starts_with('/'),
+            components: vec![],
+        };
+        for name in path.split('/') {
+            result.push(name);
+        }
+        result
+    }
+}
+
+impl FromStr for FSPath {
+    type Err = std::convert::Infallible;
+
+    fn from_str(path: &str) -> Result<FSPath, Self::Err> {
+        Ok(FSPath::from(path))
+    }
+}
+
+impl fmt::Display for FSPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.absolute, self.components.is_empty()) {
+            (true, _) => write!(f, "/{}", self.components.join("/")),
+            (false, true) => write!(f, "."),
+            (false, false) => write!(f, "{}", self.components.join("/")),
+        }
+    }
+}
+
+/// Matches `text` against a shell-style pattern, where `*` matches any run of characters and `?`
+/// matches a single one
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was seen, and the text position it currently stands for
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the `*` swallow one more character
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(FSPath::from("/a/./b/../e/").to_string(), "/a/e");
+        assert_eq!(FSPath::from("/../a").to_string(), "/a");
+        assert_eq!(FSPath::from("../a/../../b").to_string(), "../../b");
+        assert_eq!(FSPath::from("").to_string(), ".");
+        assert_eq!(FSPath::from("/"), FSPath::root());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.log", "d.log"));
+        assert!(!glob_match("*.log", "d.log.gz"));
+        assert!(glob_match("d?ext", "d.ext"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn test_join() {
+        let base = FSPath::from("/a/e");
+        assert_eq!(base.join(&FSPath::from("../../d")).to_string(), "/d");
+        assert_eq!(base.join(&FSPath::from("/x")).to_string(), "/x");
+        assert_eq!(base.child("i").to_string(), "/a/e/i");
+        assert_eq!(base.parent(), Some(FSPath::from("/a")));
+        assert_eq!(FSPath::root().parent(), None);
+        assert!(base.starts_with(&FSPath::from("/a")));
+        assert!(!FSPath::from("/ab").starts_with(&FSPath::from("/a")));
+    }
+}
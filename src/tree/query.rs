@@ -0,0 +1,420 @@
+use super::super::parse::ParseError;
+use super::path::glob_match;
+use super::render::parse_decimal_size;
+use super::{FSArena, FSKind, FSNode, FSPath, FileSystem, NodeId};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Combines the sizes of the matching nodes into a single number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::NotEqual => ordering.is_ne(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pattern {
+    Exact(String),
+    Glob(String),
+}
+
+impl Pattern {
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Pattern::Exact(exact) => exact == text,
+            Pattern::Glob(pattern) => glob_match(pattern, text),
+        }
+    }
+}
+
+/// The kinds of node `type:` tells apart, hard links being files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Directory,
+    File,
+    Symlink,
+}
+
+impl Kind {
+    fn of(kind: FSKind<&str>) -> Kind {
+        match kind {
+            FSKind::Directory => Kind::Directory,
+            FSKind::File(_) | FSKind::HardLink(..) => Kind::File,
+            FSKind::Symlink(_) => Kind::Symlink,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    /// The kind a node has to be, or must not be if the flag is false
+    Kind(Kind, bool),
+    Size(Comparison, usize),
+    Depth(Comparison, usize),
+    /// Pattern for the name, negated if the flag is false
+    Name(Pattern, bool),
+    /// Pattern for the full path, negated if the flag is false
+    Path(Pattern, bool),
+}
+
+/// Selects nodes with filters like `type:dir size<100k depth<=3 name~"*.log"`, all of which have
+/// to match. Sizes use decimal units, `100k` being 100000 bytes, unless written like `100KiB`.
+/// A leading `count`, `sum`, `min` or `max` turns the matches into a single number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub aggregate: Option<Aggregate>,
+    filters: Vec<Filter>,
+}
+
+/// The outcome of [`FSNode::query`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryResult {
+    /// Matching nodes with their total size, ordered by path
+    Matches(Vec<(FSPath, usize)>),
+    /// The aggregate, or `None` for the minimum or maximum of nothing
+    Value(Option<usize>),
+}
+
+impl fmt::Display for QueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryResult::Matches(matches) => {
+                for (path, size) in matches {
+                    writeln!(f, "{size}\t{path}")?;
+                }
+                Ok(())
+            }
+            QueryResult::Value(Some(value)) => writeln!(f, "{value}"),
+            QueryResult::Value(None) => writeln!(f, "nothing matched"),
+        }
+    }
+}
+
+/// Splits the query into words, keeping quoted parts like `name~"a b"` together
+fn words(query: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut quoted = false;
+    for (idx, ch) in query.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            ch if ch.is_whitespace() && !quoted => {
+                if let Some(start) = start.take() {
+                    words.push(&query[start..idx]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(idx);
+    }
+    if let Some(start) = start {
+        words.push(&query[start..]);
+    }
+    words
+}
+
+const OPERATORS: [(&str, Option<Comparison>); 9] = [
+    ("<=", Some(Comparison::LessOrEqual)),
+    (">=", Some(Comparison::GreaterOrEqual)),
+    ("!=", Some(Comparison::NotEqual)),
+    ("!~", None),
+    ("<", Some(Comparison::Less)),
+    (">", Some(Comparison::Greater)),
+    ("=", Some(Comparison::Equal)),
+    (":", Some(Comparison::Equal)),
+    ("~", None),
+];
+
+fn parse_filter(query: &str, word: &str) -> Result<Filter, ParseError> {
+    let error = |message: String, span: &str| ParseError::at(message, 1, query, span);
+    let Some((split, (operator, comparison))) = OPERATORS
+        .iter()
+        .filter_map(|&(op, cmp)| word.find(op).map(|idx| (idx, (op, cmp))))
+        .min_by_key(|&(idx, (op, _))| (idx, std::cmp::Reverse(op.len())))
+    else {
+        return Err(error(
+            format!("expected a filter like `size<100k`, got `{word}`"),
+            word,
+        ));
+    };
+    let field = &word[..split];
+    let raw_value = &word[split + operator.len()..];
+    let value = raw_value.trim_matches('"');
+    let unsupported = || {
+        error(
+            format!("`{operator}` can't be used with `{field}`"),
+            &word[split..split + operator.len()],
+        )
+    };
+
+    match (field, comparison) {
+        ("type", Some(Comparison::Equal | Comparison::NotEqual)) => {
+            let kind = match value {
+                "dir" | "directory" => Kind::Directory,
+                "file" => Kind::File,
+                "symlink" | "link" => Kind::Symlink,
+                _ => {
+                    return Err(error(
+                        format!("expected `dir`, `file` or `symlink`, got `{value}`"),
+                        raw_value,
+                    ))
+                }
+            };
+            Ok(Filter::Kind(kind, comparison == Some(Comparison::Equal)))
+        }
+        ("size", Some(comparison)) => match parse_decimal_size(value) {
+            Some(size) => Ok(Filter::Size(comparison, size)),
+            None => Err(error(
+                format!("`{value}` is not a size like `1234` or `100k`"),
+                raw_value,
+            )),
+        },
+        ("depth", Some(comparison)) => match value.parse() {
+            Ok(depth) => Ok(Filter::Depth(comparison, depth)),
+            Err(_) => Err(error(format!("invalid depth `{value}`"), raw_value)),
+        },
+        ("name" | "path", _) => {
+            let matches = !operator.starts_with('!');
+            let pattern = match comparison {
+                Some(Comparison::Equal | Comparison::NotEqual) => {
+                    Pattern::Exact(String::from(value))
+                }
+                Some(_) => return Err(unsupported()),
+                None => Pattern::Glob(String::from(value)),
+            };
+            Ok(if field == "name" {
+                Filter::Name(pattern, matches)
+            } else {
+                Filter::Path(pattern, matches)
+            })
+        }
+        ("type" | "size" | "depth", _) => Err(unsupported()),
+        _ => Err(error(
+            format!("unknown field `{field}`, expected `type`, `size`, `depth`, `name` or `path`"),
+            field,
+        )),
+    }
+}
+
+impl FromStr for Query {
+    type Err = ParseError;
+
+    fn from_str(query: &str) -> Result<Query, ParseError> {
+        let mut words = words(query).into_iter().peekable();
+        let aggregate = match words.peek() {
+            Some(&"count") => Some(Aggregate::Count),
+            Some(&"sum") => Some(Aggregate::Sum),
+            Some(&"min") => Some(Aggregate::Min),
+            Some(&"max") => Some(Aggregate::Max),
+            _ => None,
+        };
+        if aggregate.is_some() {
+            words.next();
+        }
+        let filters = words
+            .map(|word| parse_filter(query, word))
+            .collect::<Result<_, _>>()?;
+        Ok(Query { aggregate, filters })
+    }
+}
+
+impl Query {
+    /// Whether `node`, found at `path` below the top of `fs`, passes every filter
+    pub fn matches<F: FileSystem>(&self, fs: &F, path: &FSPath, node: F::Node) -> bool {
+        self.filters.iter().all(|filter| match filter {
+            Filter::Kind(kind, matches) => (Kind::of(fs.kind_of(node)) == *kind) == *matches,
+            Filter::Size(comparison, size) => comparison.holds(fs.size_of(node).cmp(size)),
+            Filter::Depth(comparison, depth) => {
+                comparison.holds(path.components().len().cmp(depth))
+            }
+            Filter::Name(pattern, matches) => pattern.matches(fs.name_of(node)) == *matches,
+            Filter::Path(pattern, matches) => pattern.matches(&path.to_string()) == *matches,
+        })
+    }
+
+    /// Every node of `fs` passing the filters, with its path, ordered by path
+    fn select<F: FileSystem>(&self, fs: &F) -> Vec<(FSPath, F::Node)> {
+        let mut selected: Vec<(FSPath, F::Node)> = fs
+            .paths_of()
+            .into_iter()
+            .filter(|(path, node)| self.matches(fs, path, *node))
+            .collect();
+        selected.sort_by(|a, b| a.0.cmp(&b.0));
+        selected
+    }
+
+    fn run<F: FileSystem>(&self, fs: &F) -> QueryResult {
+        let selected = self.select(fs);
+        let sizes = selected.iter().map(|&(_, node)| fs.size_of(node));
+        match self.aggregate {
+            None => QueryResult::Matches(
+                selected
+                    .iter()
+                    .map(|(path, node)| (path.clone(), fs.size_of(*node)))
+                    .collect(),
+            ),
+            Some(Aggregate::Count) => QueryResult::Value(Some(selected.len())),
+            Some(Aggregate::Sum) => QueryResult::Value(Some(sizes.sum())),
+            Some(Aggregate::Min) => QueryResult::Value(sizes.min()),
+            Some(Aggregate::Max) => QueryResult::Value(sizes.max()),
+        }
+    }
+}
+
+impl FSNode {
+    /// Every node matching `query`, with its path, ordered by path
+    pub fn select(&self, query: &Query) -> Vec<(FSPath, &FSNode)> {
+        query.select(&self)
+    }
+
+    pub fn query(&self, query: &Query) -> QueryResult {
+        query.run(&self)
+    }
+}
+
+impl FSArena {
+    /// Like [`FSNode::select`]
+    pub fn select(&self, query: &Query) -> Vec<(FSPath, NodeId)> {
+        query.select(self)
+    }
+
+    pub fn query(&self, query: &Query) -> QueryResult {
+        query.run(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const LINES: &str = "dir /
+dir /a
+dir /a/e
+584 /a/e/i
+29116 /a/f
+2557 /a/g
+62596 /a/h.lst
+14848514 /b.txt
+8504156 /c.dat
+dir /d
+4060174 /d/j
+8033020 /d/d.log
+5626152 /d/d.ext
+7214296 /d/k
+";
+
+    fn run(query: &str) -> QueryResult {
+        FSNode::from_lines(LINES)
+            .unwrap()
+            .query(&query.parse().unwrap())
+    }
+
+    #[test]
+    fn test_query() {
+        assert_eq!(
+            run("sum type:dir size<100000"),
+            QueryResult::Value(Some(95437))
+        );
+        assert_eq!(
+            run("sum type:dir size<100k"),
+            QueryResult::Value(Some(95437))
+        );
+        assert_eq!(
+            run("sum type:dir size<100kB"),
+            QueryResult::Value(Some(95437))
+        );
+        assert_eq!(run("sum type:dir size<93k"), QueryResult::Value(Some(584)));
+        assert_eq!(
+            run("sum type:dir size<93KiB"),
+            QueryResult::Value(Some(95437))
+        );
+        assert_eq!(
+            run("min type:dir size>=8381165"),
+            QueryResult::Value(Some(24933642))
+        );
+        assert_eq!(run("count type:file depth<=1"), QueryResult::Value(Some(2)));
+        assert_eq!(run("max name~\"*.l*\""), QueryResult::Value(Some(8033020)));
+        assert_eq!(run("min size>100M"), QueryResult::Value(None));
+        assert_eq!(
+            run("type!=dir path~/a/* name!~\"?\""),
+            QueryResult::Matches(vec![(FSPath::from("/a/h.lst"), 62596)])
+        );
+        assert_eq!(
+            run("depth=1 type:dir").to_string(),
+            "94853\t/a\n24933642\t/d\n"
+        );
+
+        let arena = FSArena::from(FSNode::from_lines(LINES).unwrap());
+        let query = "type!=dir path~/a/* name!~\"?\"".parse().unwrap();
+        let selected = arena.select(&query);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(arena.path_of(selected[0].1), FSPath::from("/a/h.lst"));
+        assert_eq!(arena.query(&query), run("type!=dir path~/a/* name!~\"?\""));
+    }
+
+    #[test]
+    fn test_literal_names() {
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(FSNode::file(String::from("*"), 1));
+        root.add_child(FSNode::file(String::from("a b"), 2));
+        let query = |query: &str| root.query(&query.parse().unwrap());
+        assert_eq!(query("count name=*"), QueryResult::Value(Some(1)));
+        assert_eq!(query("count name~*"), QueryResult::Value(Some(3)));
+        assert_eq!(query("sum name=\"a b\""), QueryResult::Value(Some(2)));
+
+        root.add_child(FSNode::symlink(String::from("c"), String::from("*")));
+        root.add_child(FSNode::hard_link(String::from("d"), 4, 1));
+        let query = |query: &str| root.query(&query.parse().unwrap());
+        assert_eq!(query("sum type:file"), QueryResult::Value(Some(7)));
+        assert_eq!(query("count type:symlink"), QueryResult::Value(Some(1)));
+        assert_eq!(query("count type!=link"), QueryResult::Value(Some(4)));
+    }
+
+    #[test]
+    fn test_query_errors() {
+        let error = "sum type:dir size<lots".parse::<Query>().unwrap_err();
+        assert_eq!(error.message, "`lots` is not a size like `1234` or `100k`");
+        assert_eq!((error.column, error.len), (19, 4));
+
+        let error = "colour=red".parse::<Query>().unwrap_err();
+        assert_eq!(
+            error.message,
+            "unknown field `colour`, expected `type`, `size`, `depth`, `name` or `path`"
+        );
+        let error = "name<a".parse::<Query>().unwrap_err();
+        assert_eq!(error.message, "`<` can't be used with `name`");
+        let error = "big".parse::<Query>().unwrap_err();
+        assert_eq!(
+            error.message,
+            "expected a filter like `size<100k`, got `big`"
+        );
+    }
+}
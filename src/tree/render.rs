@@ -0,0 +1,253 @@
+use super::{FSArena, FSKind, FSNode, FSPath, FileSystem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderOptions {
+    /// Don't show anything deeper than this, the starting node having depth 0
+    pub max_depth: Option<usize>,
+    /// Show sizes like `1.5M` instead of a number of bytes
+    pub human_readable: bool,
+    /// Hide nodes smaller than this
+    pub min_size: usize,
+}
+
+impl RenderOptions {
+    fn size(&self, bytes: usize) -> String {
+        if self.human_readable {
+            human_size(bytes)
+        } else {
+            bytes.to_string()
+        }
+    }
+
+    fn shows(&self, depth: usize, size: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max) && size >= self.min_size
+    }
+}
+
+const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+/// Formats a size with 1024-based units, like `du -h`
+pub fn human_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+    let mut value = bytes as f64;
+    let mut unit = UNITS[0];
+    for next in UNITS {
+        value /= 1024.0;
+        unit = next;
+        if value < 1024.0 {
+            break;
+        }
+    }
+    if value < 10.0 {
+        format!("{value:.1}{unit}")
+    } else {
+        format!("{value:.0}{unit}")
+    }
+}
+
+/// Parses a size like `1234`, `100k` or `1.5M`, using 1024-based units like [`human_size`].
+/// `KiB` style suffixes are 1024-based too, `kB` style ones are 1000-based.
+pub fn parse_size(text: &str) -> Option<usize> {
+    parse_size_in(text, 1024.0)
+}
+
+/// Like [`parse_size`], but bare suffixes are decimal: `100k` is 100000 bytes
+pub fn parse_decimal_size(text: &str) -> Option<usize> {
+    parse_size_in(text, 1000.0)
+}
+
+fn parse_size_in(text: &str, bare_base: f64) -> Option<usize> {
+    let text = text.trim().to_uppercase();
+    let (text, base, bytes_suffix) = if let Some(rest) = text.strip_suffix("IB") {
+        (rest, 1024.0, true)
+    } else if let Some(rest) = text.strip_suffix('B') {
+        (rest, 1000.0, true)
+    } else {
+        (text.as_str(), bare_base, false)
+    };
+    let (number, multiplier) = match UNITS.iter().position(|unit| text.ends_with(unit)) {
+        Some(idx) => (&text[..text.len() - 1], base.powi(idx as i32 + 1)),
+        // `iB` needs a unit in front of it, a plain `B` means bytes
+        None if bytes_suffix && base == 1024.0 => return None,
+        None => (text, 1.0),
+    };
+    let number: f64 = number.parse().ok()?;
+    if !number.is_finite() || number < 0.0 {
+        return None;
+    }
+    Some((number * multiplier).round() as usize)
+}
+
+fn label<F: FileSystem>(fs: &F, node: F::Node) -> String {
+    let name = fs.name_of(node);
+    match fs.kind_of(node) {
+        FSKind::Directory if !name.ends_with('/') => format!("{name}/"),
+        FSKind::Symlink(target) => format!("{name} -> {target}"),
+        _ => String::from(name),
+    }
+}
+
+/// Draws the tree like the `tree` command, with the size of every file and directory
+fn render_tree<F: FileSystem>(fs: &F, options: &RenderOptions) -> String {
+    let top = fs.top();
+    let mut output = format!("{} ({})\n", label(fs, top), options.size(fs.size_of(top)));
+    // Nodes still to draw, with the prefix of their line and their depth
+    let mut stack: Vec<(F::Node, String, bool, usize)> = vec![];
+    let push_children = |stack: &mut Vec<_>, node, prefix: &str, depth| {
+        let children: Vec<F::Node> = fs
+            .children_of(node)
+            .filter(|&child| options.shows(depth, fs.size_of(child)))
+            .collect();
+        let last = children.len().saturating_sub(1);
+        for (idx, child) in children.into_iter().enumerate().rev() {
+            stack.push((child, String::from(prefix), idx == last, depth));
+        }
+    };
+
+    push_children(&mut stack, top, "", 1);
+    while let Some((node, prefix, is_last, depth)) = stack.pop() {
+        let (branch, indent) = if is_last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        output.push_str(&format!(
+            "{prefix}{branch}{} ({})\n",
+            label(fs, node),
+            options.size(fs.size_of(node))
+        ));
+        push_children(&mut stack, node, &format!("{prefix}{indent}"), depth + 1);
+    }
+    output
+}
+
+/// Lists directories with their total size, largest first, like `du | sort -rn`
+fn render_du<F: FileSystem>(fs: &F, options: &RenderOptions) -> String {
+    let mut directories: Vec<(usize, FSPath)> = fs
+        .paths_of()
+        .into_iter()
+        .filter(|&(ref path, node)| {
+            fs.kind_of(node) == FSKind::Directory
+                && options.shows(path.components().len(), fs.size_of(node))
+        })
+        .map(|(path, node)| (fs.size_of(node), path))
+        .collect();
+    directories.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    directories
+        .into_iter()
+        .map(|(size, path)| format!("{}\t{path}\n", options.size(size)))
+        .collect()
+}
+
+impl FSNode {
+    /// Draws the tree like the `tree` command, with the size of every file and directory
+    pub fn render_tree(&self, options: &RenderOptions) -> String {
+        render_tree(&self, options)
+    }
+
+    /// Lists directories with their total size, largest first, like `du | sort -rn`
+    pub fn render_du(&self, options: &RenderOptions) -> String {
+        render_du(&self, options)
+    }
+}
+
+impl FSArena {
+    /// Like [`FSNode::render_tree`]
+    pub fn render_tree(&self, options: &RenderOptions) -> String {
+        render_tree(self, options)
+    }
+
+    /// Like [`FSNode::render_du`]
+    pub fn render_du(&self, options: &RenderOptions) -> String {
+        render_du(self, options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn filesystem() -> FSNode {
+        let mut e = FSNode::new(String::from("e"));
+        e.add_child(FSNode::file(String::from("i"), 584));
+        let mut a = FSNode::new(String::from("a"));
+        a.add_child(e);
+        a.add_child(FSNode::file(String::from("f"), 29116));
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(a);
+        root.add_child(FSNode::file(String::from("b.txt"), 14848514));
+        root
+    }
+
+    #[test]
+    fn test_render_tree() {
+        assert_eq!(
+            filesystem().render_tree(&RenderOptions::default()),
+            "/ (14878214)
+├── a/ (29700)
+│   ├── e/ (584)
+│   │   └── i (584)
+│   └── f (29116)
+└── b.txt (14848514)
+"
+        );
+        let options = RenderOptions {
+            max_depth: Some(1),
+            human_readable: true,
+            min_size: 1000,
+        };
+        assert_eq!(
+            filesystem().render_tree(&options),
+            "/ (14M)\n├── a/ (29K)\n└── b.txt (14M)\n"
+        );
+    }
+
+    #[test]
+    fn test_render_du() {
+        assert_eq!(
+            filesystem().render_du(&RenderOptions::default()),
+            "14878214\t/\n29700\t/a\n584\t/a/e\n"
+        );
+        let options = RenderOptions {
+            min_size: 1000,
+            ..RenderOptions::default()
+        };
+        assert_eq!(filesystem().render_du(&options), "14878214\t/\n29700\t/a\n");
+    }
+
+    #[test]
+    fn test_render_arena() {
+        let arena = FSArena::from(filesystem());
+        let options = RenderOptions {
+            human_readable: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            arena.render_tree(&options),
+            filesystem().render_tree(&options)
+        );
+        assert_eq!(arena.render_du(&options), filesystem().render_du(&options));
+    }
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(48381165), "46M");
+        assert_eq!(parse_size("100k"), Some(102400));
+        assert_eq!(parse_size("1.5M"), Some(1572864));
+        assert_eq!(parse_size("42"), Some(42));
+        assert_eq!(parse_size("x"), None);
+        assert_eq!(parse_size("100kB"), Some(100000));
+        assert_eq!(parse_size("2KiB"), Some(2048));
+        assert_eq!(parse_size("12B"), Some(12));
+        assert_eq!(parse_size("12iB"), None);
+        assert_eq!(parse_decimal_size("100k"), Some(100000));
+        assert_eq!(parse_decimal_size("1.5M"), Some(1500000));
+        assert_eq!(parse_decimal_size("100KiB"), Some(102400));
+    }
+}
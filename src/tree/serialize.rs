@@ -0,0 +1,677 @@
+use super::super::output::json_string;
+use super::super::parse::{numbered_lines, parse_number, ParseError};
+use super::{FSEntry, FSNode, FSPath, Metadata};
+use std::time::{Duration, SystemTime};
+
+/// Deeper JSON is rejected instead of risking a stack overflow while parsing it
+const MAX_JSON_DEPTH: usize = 512;
+
+/// Escapes the characters that would break a line of the line format
+fn escape_name(name: &str) -> String {
+    name.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// The fields of `metadata` that are known, each preceded by a comma
+fn json_metadata(metadata: Option<&Metadata>) -> String {
+    let Some(metadata) = metadata else {
+        return String::new();
+    };
+    let mut output = String::new();
+    let modified = metadata
+        .modified
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok());
+    if let Some(modified) = modified {
+        output.push_str(&format!(", \"modified\": {}", modified.as_secs()));
+    }
+    if let Some(permissions) = metadata.permissions {
+        output.push_str(&format!(", \"permissions\": {permissions}"));
+    }
+    if let Some(owner) = metadata.owner {
+        output.push_str(&format!(", \"owner\": {owner}"));
+    }
+    output
+}
+
+fn unescape_name(name: &str) -> String {
+    let mut result = String::new();
+    let mut chars = name.chars();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                result.push('\n');
+                chars.next();
+            }
+            ('\\', Some('t')) => {
+                result.push('\t');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                result.push('\\');
+                chars.next();
+            }
+            (ch, _) => result.push(ch),
+        }
+    }
+    result
+}
+
+impl FSNode {
+    /// Writes the tree as JSON, one node per line with children in the tree's order. Directories
+    /// look like `{"name": "a", "children": [...]}`, files like `{"name": "b", "size": 12}`,
+    /// symlinks like `{"name": "c", "target": "b"}` and hard links like
+    /// `{"name": "d", "size": 12, "link": 3}`. Known metadata is added as `modified` (seconds
+    /// since the epoch), `permissions` and `owner`.
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        // Nodes still to write with their depth, `None` closing a directory at that depth
+        let mut stack = vec![(0, Some(self))];
+        while let Some((depth, entry)) = stack.pop() {
+            let Some(node) = entry else {
+                output.push_str(&format!("\n{}]}}", "  ".repeat(depth)));
+                continue;
+            };
+            if depth > 0 && !output.ends_with('[') {
+                output.push(',');
+            }
+            if depth > 0 {
+                output.push('\n');
+            }
+            output.push_str(&"  ".repeat(depth));
+            output.push_str(&format!(
+                "{{\"name\": {}{}",
+                json_string(node.entry().name()),
+                json_metadata(node.metadata())
+            ));
+            match node.entry() {
+                FSEntry::File(_, size) => output.push_str(&format!(", \"size\": {size}}}")),
+                FSEntry::Symlink(_, target) => {
+                    output.push_str(&format!(", \"target\": {}}}", json_string(target)))
+                }
+                FSEntry::HardLink(_, size, id) => {
+                    output.push_str(&format!(", \"size\": {size}, \"link\": {id}}}"))
+                }
+                FSEntry::Directory(_) if node.is_leaf() => output.push_str(", \"children\": []}"),
+                FSEntry::Directory(_) => {
+                    output.push_str(", \"children\": [");
+                    stack.push((depth, None));
+                    let children = node.children().rev();
+                    stack.extend(children.map(|(_, c)| (depth + 1, Some(c))));
+                }
+            }
+        }
+        output.push('\n');
+        output
+    }
+
+    /// Reads a tree written by [`FSNode::to_json`]
+    pub fn from_json(input: &str) -> Result<FSNode, ParseError> {
+        let mut parser = JsonParser { input, position: 0 };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.position < input.len() {
+            return Err(parser.error("unexpected text after the tree", parser.position));
+        }
+        parser.node(&value)
+    }
+
+    /// Writes the tree as lines: the name of the root as `dir <name>`, followed by every node
+    /// below it in pre-order as `dir <path>`, `<size> <path>`, `symlink <path>\t<target>` or
+    /// `<size>@<link> <path>` for hard links. Metadata is left out.
+    pub fn to_lines(&self) -> String {
+        let mut output = format!("dir {}\n", escape_name(self.entry().name()));
+        let mut stack: Vec<(FSPath, &FSNode)> = self
+            .children()
+            .rev()
+            .map(|(name, child)| (FSPath::root().child(name), child))
+            .collect();
+        while let Some((path, node)) = stack.pop() {
+            let escaped = escape_name(&path.to_string());
+            match node.entry() {
+                FSEntry::File(_, size) => output.push_str(&format!("{size} {escaped}\n")),
+                FSEntry::Directory(_) => output.push_str(&format!("dir {escaped}\n")),
+                FSEntry::Symlink(_, target) => {
+                    output.push_str(&format!("symlink {escaped}\t{}\n", escape_name(target)))
+                }
+                FSEntry::HardLink(_, size, id) => {
+                    output.push_str(&format!("{size}@{id} {escaped}\n"))
+                }
+            }
+            stack.extend(
+                node.children()
+                    .rev()
+                    .map(|(name, child)| (path.child(name), child)),
+            );
+        }
+        output
+    }
+
+    /// Reads a tree written by [`FSNode::to_lines`]. Every entry's directory has to be listed
+    /// before it.
+    pub fn from_lines(input: &str) -> Result<FSNode, ParseError> {
+        let mut lines = numbered_lines(input).filter(|(_, line)| !line.trim().is_empty());
+        let root = match lines.next() {
+            Some((_, line)) if line.starts_with("dir ") => unescape_name(&line[4..]),
+            Some((line_number, line)) => {
+                return Err(ParseError::new(
+                    "expected the root directory like `dir /`",
+                    line_number,
+                    line,
+                ))
+            }
+            None => return Err(ParseError::new("expected the root directory", 1, "")),
+        };
+        let mut root = FSNode::new(root);
+
+        for (line_number, line) in lines {
+            let Some((kind, path)) = line.split_once(' ') else {
+                return Err(ParseError::new(
+                    "expected an entry like `dir /a` or `1234 /b.txt`",
+                    line_number,
+                    line,
+                ));
+            };
+            let error = |message: &str| ParseError::at(message, line_number, line, path);
+            let (path, target) = match (kind, path.split_once('\t')) {
+                ("symlink", Some((path, target))) => (path, Some(unescape_name(target))),
+                ("symlink", None) => return Err(error("expected a tab before the target")),
+                _ => (path, None),
+            };
+            let fs_path = FSPath::from(unescape_name(path).as_str());
+            let (Some(parent), Some(name)) = (fs_path.parent(), fs_path.file_name()) else {
+                return Err(error("expected an absolute path below the root"));
+            };
+            if !fs_path.is_absolute() {
+                return Err(error("expected an absolute path below the root"));
+            }
+            let name = String::from(name);
+            let node = match (kind, target) {
+                ("dir", _) => FSNode::new(name),
+                (_, Some(target)) => FSNode::symlink(name, target),
+                (size, None) => match size.split_once('@') {
+                    Some((size, id)) => FSNode::hard_link(
+                        name,
+                        parse_number(line_number, line, size)?,
+                        parse_number(line_number, line, id)?,
+                    ),
+                    None => FSNode::file(name, parse_number(line_number, line, size)?),
+                },
+            };
+            match root.get(&parent) {
+                Some(directory) if directory.is_directory() => {}
+                _ => return Err(error("the parent directory was not listed before")),
+            }
+            if root.get(&fs_path).is_some() {
+                return Err(error("listed twice"));
+            }
+            root.update(&parent, |directory| directory.add_child(node));
+        }
+        Ok(root)
+    }
+
+    /// Generates a day 7 terminal session that lists every directory once. Symlinks are left
+    /// out and hard links listed as plain files, since a transcript has no way to show them.
+    pub fn to_transcript(&self) -> String {
+        let mut output = String::from("$ cd /\n");
+        // `None` stands for going back up, which is left out at the very end
+        let mut stack = vec![Some(self)];
+        let mut pending_up = 0;
+        while let Some(entry) = stack.pop() {
+            let Some(node) = entry else {
+                pending_up += 1;
+                continue;
+            };
+            output.push_str(&"$ cd ..\n".repeat(pending_up));
+            pending_up = 0;
+            if !std::ptr::eq(node, self) {
+                output.push_str(&format!("$ cd {}\n", node.entry().name()));
+            }
+            output.push_str("$ ls\n");
+            let children: Vec<&FSNode> = node.children().map(|(_, child)| child).collect();
+            for child in &children {
+                match child.entry() {
+                    FSEntry::File(name, size) => output.push_str(&format!("{size} {name}\n")),
+                    FSEntry::HardLink(name, size, _) => {
+                        output.push_str(&format!("{size} {name}\n"))
+                    }
+                    FSEntry::Directory(name) => output.push_str(&format!("dir {name}\n")),
+                    FSEntry::Symlink(..) => {}
+                }
+            }
+            for child in children.into_iter().rev() {
+                if child.is_directory() {
+                    stack.push(None);
+                    stack.push(Some(child));
+                }
+            }
+        }
+        output
+    }
+}
+
+#[derive(Debug)]
+enum JsonKind {
+    /// `null`, `true` or `false`, none of which a tree uses
+    Literal,
+    /// The digits as written, so sizes don't lose precision
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// A JSON value together with the byte offset it starts at, for error messages
+#[derive(Debug)]
+struct Json {
+    at: usize,
+    kind: JsonKind,
+}
+
+struct JsonParser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: impl Into<String>, at: usize) -> ParseError {
+        let at = at.min(self.input.len());
+        let line_start = self.input[..at].rfind('\n').map_or(0, |idx| idx + 1);
+        let line_end = self.input[at..]
+            .find('\n')
+            .map_or(self.input.len(), |idx| at + idx);
+        let line = &self.input[line_start..line_end];
+        let span_end = self.input[at..line_end]
+            .chars()
+            .next()
+            .map_or(at, |ch| at + ch.len_utf8());
+        let line_number = self.input[..at].matches('\n').count() + 1;
+        ParseError::at(
+            message,
+            line_number,
+            line,
+            &self.input[at..span_end.max(at)],
+        )
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if !self.rest().starts_with(token) {
+            return Err(self.error(format!("expected `{token}`"), self.position));
+        }
+        self.position += token.len();
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, ParseError> {
+        self.skip_whitespace();
+        let at = self.position;
+        if depth > MAX_JSON_DEPTH {
+            return Err(self.error("nested too deeply", at));
+        }
+        let kind = match self.rest().chars().next() {
+            Some('{') => {
+                self.position += 1;
+                let mut fields = vec![];
+                self.skip_whitespace();
+                if self.rest().starts_with('}') {
+                    self.position += 1;
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(":")?;
+                        fields.push((key, self.value(depth + 1)?));
+                        if self.separator('}')? {
+                            break;
+                        }
+                    }
+                }
+                JsonKind::Object(fields)
+            }
+            Some('[') => {
+                self.position += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if self.rest().starts_with(']') {
+                    self.position += 1;
+                } else {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        if self.separator(']')? {
+                            break;
+                        }
+                    }
+                }
+                JsonKind::Array(items)
+            }
+            Some('"') => JsonKind::String(self.string()?),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => {
+                let len = self
+                    .rest()
+                    .find(|ch: char| !(ch.is_ascii_alphanumeric() || "+-.".contains(ch)))
+                    .unwrap_or(self.rest().len());
+                let number = String::from(&self.rest()[..len]);
+                self.position += len;
+                JsonKind::Number(number)
+            }
+            _ => {
+                let literal = ["null", "true", "false"]
+                    .into_iter()
+                    .find(|literal| self.rest().starts_with(literal));
+                let Some(literal) = literal else {
+                    return Err(self.error("expected a JSON value", at));
+                };
+                self.position += literal.len();
+                JsonKind::Literal
+            }
+        };
+        Ok(Json { at, kind })
+    }
+
+    /// Consumes a `,` and returns `false`, or consumes `close` and returns `true`
+    fn separator(&mut self, close: char) -> Result<bool, ParseError> {
+        self.skip_whitespace();
+        match self.rest().chars().next() {
+            Some(',') => {
+                self.position += 1;
+                Ok(false)
+            }
+            Some(ch) if ch == close => {
+                self.position += 1;
+                Ok(true)
+            }
+            _ => Err(self.error(format!("expected `,` or `{close}`"), self.position)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let start = self.position;
+        if !self.rest().starts_with('"') {
+            return Err(self.error("expected a string", start));
+        }
+        self.position += 1;
+        let mut result = String::new();
+        loop {
+            let Some(ch) = self.rest().chars().next() else {
+                return Err(self.error("unterminated string", start));
+            };
+            self.position += ch.len_utf8();
+            match ch {
+                '"' => return Ok(result),
+                '\\' => {
+                    let escape_at = self.position - 1;
+                    let escaped = self.rest().chars().next();
+                    self.position += escaped.map_or(0, char::len_utf8);
+                    let unescaped = match escaped {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => self
+                            .unicode_escape()
+                            .ok_or_else(|| self.error("invalid `\\u` escape", escape_at))?,
+                        _ => return Err(self.error("invalid escape", escape_at)),
+                    };
+                    result.push(unescaped);
+                }
+                ch => result.push(ch),
+            }
+        }
+    }
+
+    /// The character of a `\u` escape whose `\u` has been consumed, combining a surrogate pair
+    /// written as two escapes
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex_code()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+        if !self.rest().starts_with("\\u") {
+            return None;
+        }
+        self.position += 2;
+        let low = self
+            .hex_code()
+            .filter(|low| (0xDC00..0xE000).contains(low))?;
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    /// Four hex digits
+    fn hex_code(&mut self) -> Option<u32> {
+        let code = u32::from_str_radix(self.rest().get(..4)?, 16).ok()?;
+        self.position += 4;
+        Some(code)
+    }
+
+    /// The number in an optional field
+    fn number<T: std::str::FromStr>(
+        &self,
+        field: Option<&Json>,
+        what: &str,
+    ) -> Result<Option<T>, ParseError> {
+        let Some(value) = field else {
+            return Ok(None);
+        };
+        match &value.kind {
+            JsonKind::Number(number) => match number.parse() {
+                Ok(number) => Ok(Some(number)),
+                Err(_) => Err(self.error(format!("invalid {what} `{number}`"), value.at)),
+            },
+            _ => Err(self.error(format!("expected the {what} as a number"), value.at)),
+        }
+    }
+
+    fn node(&self, value: &Json) -> Result<FSNode, ParseError> {
+        let JsonKind::Object(fields) = &value.kind else {
+            return Err(self.error("expected a node like `{\"name\": ...}`", value.at));
+        };
+        let field = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+        let name = match field("name") {
+            Some(Json {
+                kind: JsonKind::String(name),
+                ..
+            }) => name.clone(),
+            Some(other) => return Err(self.error("expected the name as a string", other.at)),
+            None => return Err(self.error("missing `name`", value.at)),
+        };
+        let size = self.number(field("size"), "size")?;
+        let link = self.number(field("link"), "link")?;
+        let node = match (size, link, field("target"), field("children")) {
+            (Some(size), None, None, None) => FSNode::file(name, size),
+            (Some(size), Some(id), None, None) => FSNode::hard_link(name, size, id),
+            (None, None, Some(target), None) => match &target.kind {
+                JsonKind::String(target) => FSNode::symlink(name, target.clone()),
+                _ => return Err(self.error("expected the target as a string", target.at)),
+            },
+            (None, None, None, Some(children)) => {
+                let JsonKind::Array(children) = &children.kind else {
+                    return Err(self.error("expected the children as an array", children.at));
+                };
+                let mut directory = FSNode::new(name);
+                for child in children {
+                    let child = self.node(child)?;
+                    if directory.child(&child.name()).is_some() {
+                        return Err(
+                            self.error(format!("`{}` is listed twice", child.name()), value.at)
+                        );
+                    }
+                    directory.add_child(child);
+                }
+                directory
+            }
+            _ => {
+                return Err(self.error(
+                    "expected either `size` for a file, `size` and `link` for a hard link, \
+                     `target` for a symlink or `children` for a directory",
+                    value.at,
+                ))
+            }
+        };
+        let modified = match self.number(field("modified"), "modification time")? {
+            Some(secs) => Some(
+                SystemTime::UNIX_EPOCH
+                    .checked_add(Duration::from_secs(secs))
+                    .ok_or_else(|| {
+                        let at = field("modified").map_or(value.at, |modified| modified.at);
+                        self.error(format!("modification time `{secs}` is too far away"), at)
+                    })?,
+            ),
+            None => None,
+        };
+        let metadata = Metadata {
+            modified,
+            permissions: self.number(field("permissions"), "permissions")?,
+            owner: self.number(field("owner"), "owner")?,
+        };
+        if metadata == Metadata::default() {
+            Ok(node)
+        } else {
+            Ok(node.with_metadata(metadata))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const LINES: &str = "dir /
+dir /a
+dir /a/e
+584 /a/e/i
+29116 /a/f
+14848514 /b.txt
+";
+
+    #[test]
+    fn test_lines() {
+        let root = FSNode::from_lines(LINES).unwrap();
+        assert_eq!(root.size_below(), 14878214);
+        assert_eq!(root.to_lines(), LINES);
+
+        let error = FSNode::from_lines("dir /\n5 /a/b").unwrap_err();
+        assert_eq!(error.message, "the parent directory was not listed before");
+        assert_eq!((error.line, error.column), (2, 3));
+        let error = FSNode::from_lines("dir /\n5 a").unwrap_err();
+        assert_eq!(error.message, "expected an absolute path below the root");
+
+        let mut odd = FSNode::new(String::from("/"));
+        odd.add_child(FSNode::file(String::from("line\nbreak\\"), 1));
+        assert_eq!(odd.to_lines(), "dir /\n1 /line\\nbreak\\\\\n");
+        assert_eq!(FSNode::from_lines(&odd.to_lines()), Ok(odd));
+    }
+
+    #[test]
+    fn test_json() {
+        let root = FSNode::from_lines(LINES).unwrap();
+        let json = root.to_json();
+        assert_eq!(
+            json,
+            r#"{"name": "/", "children": [
+  {"name": "a", "children": [
+    {"name": "e", "children": [
+      {"name": "i", "size": 584}
+    ]},
+    {"name": "f", "size": 29116}
+  ]},
+  {"name": "b.txt", "size": 14848514}
+]}
+"#
+        );
+        assert_eq!(FSNode::from_json(&json), Ok(root));
+        assert_eq!(
+            FSNode::from_json(r#"{"name": "/", "children": []}"#),
+            Ok(FSNode::new(String::from("/")))
+        );
+        let emoji = FSNode::from_json(r#"{"name": "\ud83d\ude00", "size": 1}"#).unwrap();
+        assert_eq!(emoji.name(), "\u{1f600}");
+        assert_eq!(FSNode::from_json(&emoji.to_json()), Ok(emoji));
+
+        let error =
+            FSNode::from_json("{\"name\": \"/\",\n \"children\": [{\"name\": 1}]}").unwrap_err();
+        assert_eq!(error.message, "expected the name as a string");
+        assert_eq!((error.line, error.column), (2, 24));
+        let error = FSNode::from_json(
+            "{\"name\": \"x\",\n \"size\": 1, \"modified\": 18446744073709551615}",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.message,
+            "modification time `18446744073709551615` is too far away"
+        );
+        assert_eq!((error.line, error.column), (2, 25));
+        let error = FSNode::from_json("{\"name\": \"\\ud83d\", \"size\": 1}").unwrap_err();
+        assert_eq!(error.message, "invalid `\\u` escape");
+        let error = FSNode::from_json("{\"name\": \"x\", \"size\": 1} x").unwrap_err();
+        assert_eq!(error.message, "unexpected text after the tree");
+    }
+
+    #[test]
+    fn test_links() {
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(FSNode::hard_link(String::from("a"), 10, 4));
+        root.add_child(FSNode::hard_link(String::from("b"), 10, 4));
+        root.add_child(FSNode::symlink(
+            String::from("c\td"),
+            String::from("../a b"),
+        ));
+        let lines = root.to_lines();
+        assert_eq!(lines, "dir /\n10@4 /a\n10@4 /b\nsymlink /c\\td\t../a b\n");
+        assert_eq!(FSNode::from_lines(&lines).as_ref(), Ok(&root));
+        assert_eq!(root.to_transcript(), "$ cd /\n$ ls\n10 a\n10 b\n");
+
+        let root = root.with_metadata(Metadata {
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000)),
+            permissions: Some(0o755),
+            owner: None,
+        });
+        let json = root.to_json();
+        assert_eq!(
+            json,
+            r#"{"name": "/", "modified": 1600000000, "permissions": 493, "children": [
+  {"name": "a", "size": 10, "link": 4},
+  {"name": "b", "size": 10, "link": 4},
+  {"name": "c\td", "target": "../a b"}
+]}
+"#
+        );
+        let parsed = FSNode::from_json(&json).unwrap();
+        assert_eq!(parsed.size_below(), 10);
+        assert_eq!(parsed, root);
+    }
+
+    #[test]
+    fn test_transcript() {
+        let root = FSNode::from_lines(LINES).unwrap();
+        assert_eq!(
+            root.to_transcript(),
+            "$ cd /
+$ ls
+dir a
+14848514 b.txt
+$ cd a
+$ ls
+dir e
+29116 f
+$ cd e
+$ ls
+584 i
+"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use super::{Measure, Tree};
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+fn children<K: Eq + Hash + Clone, T, M: Measure<T>>(
+    node: &Tree<K, T, M>,
+) -> impl DoubleEndedIterator<Item = &Tree<K, T, M>> {
+    node.children().map(|(_, child)| child)
+}
+
+/// Breadth-first iterator yielding `(depth, node)`, the starting node having depth 0
+pub struct Bfs<'a, K, T, M: Measure<T>> {
+    queue: VecDeque<(usize, &'a Tree<K, T, M>)>,
+}
+
+impl<'a, K: Eq + Hash + Clone, T, M: Measure<T>> Iterator for Bfs<'a, K, T, M> {
+    type Item = (usize, &'a Tree<K, T, M>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, node) = self.queue.pop_front()?;
+        self.queue
+            .extend(children(node).map(|child| (depth + 1, child)));
+        Some((depth, node))
+    }
+}
+
+/// Depth-first iterator yielding every node before its children
+pub struct Preorder<'a, K, T, M: Measure<T>> {
+    stack: Vec<(usize, &'a Tree<K, T, M>)>,
+}
+
+impl<'a, K: Eq + Hash + Clone, T, M: Measure<T>> Iterator for Preorder<'a, K, T, M> {
+    type Item = (usize, &'a Tree<K, T, M>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, node) = self.stack.pop()?;
+        self.stack
+            .extend(children(node).rev().map(|child| (depth + 1, child)));
+        Some((depth, node))
+    }
+}
+
+/// Depth-first iterator yielding every node after its children
+pub struct Postorder<'a, K, T, M: Measure<T>> {
+    /// Nodes together with whether their children were already pushed
+    stack: Vec<(usize, &'a Tree<K, T, M>, bool)>,
+}
+
+impl<'a, K: Eq + Hash + Clone, T, M: Measure<T>> Iterator for Postorder<'a, K, T, M> {
+    type Item = (usize, &'a Tree<K, T, M>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (depth, node, expanded) = self.stack.pop()?;
+            if expanded || node.is_leaf() {
+                return Some((depth, node));
+            }
+            self.stack.push((depth, node, true));
+            self.stack
+                .extend(children(node).rev().map(|child| (depth + 1, child, false)));
+        }
+    }
+}
+
+/// What [`Tree::walk`] should do after visiting a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    /// Don't descend into the node that was just visited
+    SkipChildren,
+    Stop,
+}
+
+impl<K: Eq + Hash + Clone, T, M: Measure<T>> Tree<K, T, M> {
+    pub fn iter_bfs(&self) -> Bfs<'_, K, T, M> {
+        Bfs {
+            queue: VecDeque::from([(0, self)]),
+        }
+    }
+
+    pub fn iter_preorder(&self) -> Preorder<'_, K, T, M> {
+        Preorder {
+            stack: vec![(0, self)],
+        }
+    }
+
+    pub fn iter_postorder(&self) -> Postorder<'_, K, T, M> {
+        Postorder {
+            stack: vec![(0, self, false)],
+        }
+    }
+
+    /// Visits nodes in pre-order, letting `visitor` prune subtrees or stop early
+    pub fn walk<'a, F>(&'a self, mut visitor: F)
+    where
+        F: FnMut(usize, &'a Tree<K, T, M>) -> Visit,
+    {
+        let mut stack = vec![(0, self)];
+        while let Some((depth, node)) = stack.pop() {
+            match visitor(depth, node) {
+                Visit::Continue => {
+                    stack.extend(children(node).rev().map(|child| (depth + 1, child)))
+                }
+                Visit::SkipChildren => {}
+                Visit::Stop => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::FSNode;
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// `/` containing `a/` (with `a/b` and `a/c/`, which holds `a/c/d`)
+    fn tree() -> FSNode {
+        let mut c = FSNode::new(String::from("c"));
+        c.add_child(FSNode::file(String::from("d"), 1));
+        let mut a = FSNode::new(String::from("a"));
+        a.add_child(FSNode::file(String::from("b"), 2));
+        a.add_child(c);
+        let mut root = FSNode::new(String::from("/"));
+        root.add_child(a);
+        root
+    }
+
+    fn names<'a>(nodes: impl Iterator<Item = (usize, &'a FSNode)>) -> Vec<(usize, String)> {
+        nodes.map(|(depth, node)| (depth, node.name())).collect()
+    }
+
+    #[test]
+    fn test_depths() {
+        let root = tree();
+        assert_eq!(
+            names(root.iter_bfs()),
+            vec![
+                (0, String::from("/")),
+                (1, String::from("a")),
+                (2, String::from("b")),
+                (2, String::from("c")),
+                (3, String::from("d")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_order() {
+        let root = tree();
+        let order = |nodes: Vec<(usize, String)>| -> Vec<String> {
+            nodes.into_iter().map(|(_, name)| name).collect()
+        };
+        assert_eq!(
+            order(names(root.iter_preorder())),
+            vec!["/", "a", "b", "c", "d"]
+        );
+        assert_eq!(
+            order(names(root.iter_postorder())),
+            vec!["b", "d", "c", "a", "/"]
+        );
+    }
+
+    #[test]
+    fn test_walk_pruning() {
+        let root = tree();
+        let mut visited = vec![];
+        root.walk(|_, node| {
+            visited.push(node.name());
+            if node.name() == "c" {
+                Visit::SkipChildren
+            } else {
+                Visit::Continue
+            }
+        });
+        assert_eq!(visited, vec!["/", "a", "b", "c"]);
+
+        let mut count = 0;
+        root.walk(|_, _| {
+            count += 1;
+            Visit::Stop
+        });
+        assert_eq!(count, 1);
+    }
+}
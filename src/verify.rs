@@ -0,0 +1,302 @@
+use super::answer::Answer;
+use super::runner::Outcome;
+use std::collections::HashMap;
+use std::fmt;
+
+pub const DEFAULT_ANSWERS_FILE: &str = "answers.toml";
+
+/// Known-correct answers keyed by `(day, part)`
+pub type AnswerKey = HashMap<(u8, u8), Answer>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnswersFileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AnswersFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AnswersFileError {}
+
+/// Parses the small TOML subset used for the answers file:
+///
+/// ```toml
+/// [day05]
+/// part1 = "CMZ"
+/// part2 = 42
+///
+/// [day10]
+/// part2 = ["##..", "..##"]
+/// ```
+pub fn parse_answers(text: &str) -> Result<AnswerKey, AnswersFileError> {
+    let mut key = AnswerKey::new();
+    let mut current_day = None;
+
+    for (idx, raw_line) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        let error = |message: String| AnswersFileError {
+            line: line_number,
+            message,
+        };
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let day = section
+                .trim()
+                .strip_prefix("day")
+                .and_then(|day| day.parse::<u8>().ok())
+                .ok_or_else(|| error(format!("expected a section like `[day07]`, got `{line}`")))?;
+            current_day = Some(day);
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(error(format!("expected `partN = <answer>`, got `{line}`")));
+        };
+        let part = match name.trim() {
+            "part1" => 1,
+            "part2" => 2,
+            other => return Err(error(format!("unknown key `{other}`"))),
+        };
+        let Some(day) = current_day else {
+            return Err(error(String::from(
+                "answer given before any `[dayNN]` section",
+            )));
+        };
+        let answer = parse_value(value.trim()).map_err(error)?;
+        if key.insert((day, part), answer).is_some() {
+            return Err(error(format!("duplicate answer for day {day} part {part}")));
+        }
+    }
+
+    Ok(key)
+}
+
+fn parse_value(value: &str) -> Result<Answer, String> {
+    if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        let mut rows = vec![];
+        let mut rest = items.trim();
+        while !rest.is_empty() {
+            let (row, remainder) = parse_string(rest)?;
+            rows.push(row);
+            rest = remainder.trim_start();
+            rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+        }
+        return Ok(Answer::Picture(rows));
+    }
+
+    if value.starts_with('"') {
+        let (text, remainder) = parse_string(value)?;
+        if !remainder.trim().is_empty() && !remainder.trim().starts_with('#') {
+            return Err(format!("unexpected `{}` after string", remainder.trim()));
+        }
+        return Ok(Answer::Text(text));
+    }
+
+    let number = value.split('#').next().unwrap_or_default().trim();
+    number
+        .replace('_', "")
+        .parse()
+        .map(Answer::Number)
+        .map_err(|_| format!("`{value}` is neither a number, a string nor a list of strings"))
+}
+
+/// Parses a double-quoted string at the start of `input`, returning it and the remaining text
+fn parse_string(input: &str) -> Result<(String, &str), String> {
+    let Some(body) = input.strip_prefix('"') else {
+        return Err(format!("expected a string, got `{input}`"));
+    };
+    let mut result = String::new();
+    let mut chars = body.char_indices();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' => return Ok((result, &body[idx + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => result.push('\n'),
+                Some((_, 't')) => result.push('\t'),
+                Some((_, '"')) => result.push('"'),
+                Some((_, '\\')) => result.push('\\'),
+                Some((_, other)) => return Err(format!("unsupported escape `\\{other}`")),
+                None => break,
+            },
+            ch => result.push(ch),
+        }
+    }
+    Err(format!("unterminated string `{input}`"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail { expected: Answer, actual: Answer },
+    Missing,
+    NoInput,
+    Error(String),
+}
+
+impl Status {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Status::Fail { .. } | Status::Error(_))
+    }
+}
+
+fn check(expected: Option<&Answer>, actual: &Answer) -> Status {
+    match expected {
+        None => Status::Missing,
+        // Compare the rendered answers, so `"42"` in the file matches a numeric 42
+        Some(expected) if expected.to_string() == actual.to_string() => Status::Pass,
+        Some(expected) => Status::Fail {
+            expected: expected.clone(),
+            actual: actual.clone(),
+        },
+    }
+}
+
+pub fn verify(outcomes: &[(u8, Outcome)], key: &AnswerKey) -> Vec<(u8, u8, Status)> {
+    let mut report = vec![];
+    for (day, outcome) in outcomes {
+        for part in [1, 2] {
+            let status = match outcome {
+                Outcome::Solved(run) => {
+                    let actual = if part == 1 {
+                        &run.answers.part1
+                    } else {
+                        &run.answers.part2
+                    };
+                    check(key.get(&(*day, part)), actual)
+                }
+                Outcome::NoInput(_) => Status::NoInput,
+                Outcome::Invalid(err) => Status::Error(format!(
+                    "invalid input on line {}: {}",
+                    err.line, err.message
+                )),
+                Outcome::Failed(message) => Status::Error(message.clone()),
+            };
+            report.push((*day, part, status));
+        }
+    }
+    report
+}
+
+fn one_line(answer: &Answer) -> String {
+    answer.to_string().replace('\n', "\\n")
+}
+
+pub fn render_report(report: &[(u8, u8, Status)]) -> String {
+    let mut output = String::new();
+    for (day, part, status) in report {
+        let line = match status {
+            Status::Pass => String::from("PASS"),
+            Status::Fail { expected, actual } => format!(
+                "FAIL  expected {}, got {}",
+                one_line(expected),
+                one_line(actual)
+            ),
+            Status::Missing => String::from("MISSING  no known answer"),
+            Status::NoInput => String::from("SKIP  no input"),
+            Status::Error(message) => format!("FAIL  {message}"),
+        };
+        output.push_str(&format!("day {day:>2} part {part}: {line}\n"));
+    }
+
+    let count = |predicate: fn(&Status) -> bool| {
+        report
+            .iter()
+            .filter(|(_, _, status)| predicate(status))
+            .count()
+    };
+    output.push_str(&format!(
+        "\n{} passed, {} failed, {} missing, {} skipped\n",
+        count(|status| *status == Status::Pass),
+        count(Status::is_failure),
+        count(|status| *status == Status::Missing),
+        count(|status| *status == Status::NoInput),
+    ));
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::answer::Answers;
+    use super::super::solutions::{Run, Timings};
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const ANSWERS: &str = r##"
+# Known answers
+[day05]
+part1 = "CMZ"
+part2 = 1_000
+
+[day10]
+part2 = ["#.", ".#"]
+"##;
+
+    #[test]
+    fn test_parse_answers() {
+        assert_eq!(
+            parse_answers(ANSWERS),
+            Ok(HashMap::from([
+                ((5, 1), Answer::Text(String::from("CMZ"))),
+                ((5, 2), Answer::Number(1000)),
+                (
+                    (10, 2),
+                    Answer::Picture(vec![String::from("#."), String::from(".#")])
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_answers_errors() {
+        assert_eq!(
+            parse_answers("part1 = 3").unwrap_err().to_string(),
+            "line 1: answer given before any `[dayNN]` section"
+        );
+        assert_eq!(
+            parse_answers("[day01]\npart3 = 3").unwrap_err().to_string(),
+            "line 2: unknown key `part3`"
+        );
+        assert_eq!(parse_answers("[day01]\npart1 = \"abc").unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_verify() {
+        let run = Run {
+            answers: Answers {
+                part1: Answer::Text(String::from("MCD")),
+                part2: Answer::Number(1000),
+            },
+            timings: Timings::default(),
+        };
+        let key = parse_answers(ANSWERS).unwrap();
+        let report = verify(
+            &[(5, Outcome::Solved(run.clone())), (6, Outcome::Solved(run))],
+            &key,
+        );
+        assert_eq!(
+            report,
+            vec![
+                (
+                    5,
+                    1,
+                    Status::Fail {
+                        expected: Answer::Text(String::from("CMZ")),
+                        actual: Answer::Text(String::from("MCD"))
+                    }
+                ),
+                (5, 2, Status::Pass),
+                (6, 1, Status::Missing),
+                (6, 2, Status::Missing),
+            ]
+        );
+        assert!(render_report(&report).ends_with("1 passed, 1 failed, 2 missing, 0 skipped\n"));
+    }
+}